@@ -1 +1 @@
-cat /tmp/__exaltedcombat | jq -r '.log[] '
+cargo run --quiet -- --export-log
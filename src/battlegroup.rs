@@ -0,0 +1,112 @@
+use crate::dice::{DicePool, RollResult};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+//How well trained a battle group is
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Drill {
+    Poor,
+    Average,
+    Elite,
+}
+
+impl Drill {
+    pub fn defense_bonus(&self) -> i32 {
+        match self {
+            Drill::Poor => 0,
+            Drill::Average => 1,
+            Drill::Elite => 2,
+        }
+    }
+
+    //Successes a rout check needs to keep the group together
+    pub fn rout_difficulty(&self) -> i32 {
+        match self {
+            Drill::Poor => 3,
+            Drill::Average => 2,
+            Drill::Elite => 1,
+        }
+    }
+}
+
+//A mass of individuals fighting as one combatant, built on top of a Character
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct BattleGroup {
+    pub size: i32,
+    pub drill: Drill,
+    #[serde(default)]
+    pub might: i32,
+    #[serde(default)]
+    pub magnitude: i32,
+    #[serde(default = "BattleGroup::default_willpower")]
+    pub willpower: i32,
+    #[serde(default)]
+    pub routed: bool,
+}
+
+impl BattleGroup {
+    pub fn new(size: i32, drill: Drill, might: i32) -> BattleGroup {
+        BattleGroup {
+            size,
+            drill,
+            might,
+            magnitude: 0,
+            willpower: BattleGroup::default_willpower(),
+            routed: false,
+        }
+    }
+    fn default_willpower() -> i32 { 5 }
+
+    //Magnitude of a full group, the health levels of one member plus Size
+    pub fn max_magnitude(&self, levels: i32) -> i32 {
+        levels + self.size
+    }
+
+    pub fn restore(&mut self, levels: i32) {
+        self.magnitude = self.max_magnitude(levels);
+        self.routed = false;
+    }
+
+    //Bonus dice on attacks and damage for the group's numbers and strength
+    pub fn attack_bonus(&self) -> i32 {
+        self.size + self.might
+    }
+
+    //Removes Magnitude, dropping a Size each time it runs out, excess damage is lost
+    pub fn take(&mut self, levels: i32, damage: i32) -> i32 {
+        if damage <= 0 || self.size <= 0 {
+            return 0;
+        }
+        self.magnitude -= damage;
+        if self.magnitude <= 0 {
+            self.size -= 1;
+            self.magnitude = if self.size > 0 { self.max_magnitude(levels) } else { 0 };
+        }
+        damage
+    }
+
+    pub fn heal(&mut self, levels: i32, amount: i32) {
+        self.magnitude = std::cmp::min(self.magnitude + amount, self.max_magnitude(levels));
+    }
+
+    pub fn rout_check<R: Rng + ?Sized>(&mut self, rng: &mut R) -> RollResult {
+        let roll = DicePool::new(self.willpower).roll(rng);
+        self.routed = roll.successes < self.drill.rout_difficulty();
+        roll
+    }
+
+    pub fn incapacitated(&self) -> bool {
+        self.size <= 0 || self.routed
+    }
+}
+
+impl fmt::Display for BattleGroup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.routed {
+            write!(f, "Routed")
+        } else {
+            write!(f, "M{} S{}", self.magnitude, self.size)
+        }
+    }
+}
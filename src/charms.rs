@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+//What activating a charm costs, written like "5m, 1wp, 2i" in charm files
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(try_from = "CostRecord")]
+pub struct Cost {
+    #[serde(default)]
+    pub motes: i32,
+    #[serde(default)]
+    pub willpower: i32,
+    #[serde(default)]
+    pub initiative: i32,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CostRecord {
+    Text(String),
+    Fields {
+        #[serde(default)]
+        motes: i32,
+        #[serde(default)]
+        willpower: i32,
+        #[serde(default)]
+        initiative: i32,
+    },
+}
+
+impl TryFrom<CostRecord> for Cost {
+    type Error = String;
+
+    fn try_from(record: CostRecord) -> Result<Cost, String> {
+        match record {
+            CostRecord::Text(x) => Cost::parse(&x).ok_or(format!("Invalid charm cost {}", x)),
+            CostRecord::Fields { motes, willpower, initiative } => Ok(Cost {
+                motes,
+                willpower,
+                initiative,
+            }),
+        }
+    }
+}
+
+impl Cost {
+    pub fn parse(text: &str) -> Option<Cost> {
+        let mut cost = Cost::default();
+        for part in text.split(|x: char| x == ',' || x.is_whitespace()).filter(|x| !x.is_empty()) {
+            let split = part.find(|x: char| !x.is_ascii_digit())?;
+            let amount = part[..split].parse::<i32>().ok()?;
+            match &part[split..] {
+                "m" => cost.motes += amount,
+                "wp" => cost.willpower += amount,
+                "i" => cost.initiative += amount,
+                _ => return None,
+            }
+        }
+        Some(cost)
+    }
+}
+
+impl fmt::Display for Cost {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = Vec::new();
+        for (amount, suffix) in [(self.motes, "m"), (self.willpower, "wp"), (self.initiative, "i")] {
+            if amount > 0 {
+                parts.push(format!("{}{}", amount, suffix));
+            }
+        }
+        match parts.is_empty() {
+            true => write!(f, "-"),
+            false => write!(f, "{}", parts.join(", ")),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharmType {
+    Simple,
+    Supplemental,
+    Reflexive,
+    Permanent,
+}
+
+//How long an effect lasts once started
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Duration {
+    #[default]
+    Instant,
+    Turns(i32),
+    Rounds(i32),
+    Scene,
+    Indefinite,
+}
+
+impl Duration {
+    //Reads a duration like "2t", "3r", "scene" or "instant", blank lasts indefinitely
+    pub fn parse(text: &str) -> Option<Duration> {
+        let text = text.trim().to_lowercase();
+        match text.as_str() {
+            "" | "indefinite" => return Some(Duration::Indefinite),
+            "scene" => return Some(Duration::Scene),
+            "instant" => return Some(Duration::Instant),
+            _ => {}
+        }
+        let amount = |x: &str| x.parse::<i32>().ok().filter(|x| *x > 0);
+        match (text.strip_suffix('t'), text.strip_suffix('r')) {
+            (Some(x), _) => amount(x).map(Duration::Turns),
+            (_, Some(x)) => amount(x).map(Duration::Rounds),
+            _ => None,
+        }
+    }
+
+    //Counts down a character's turn, returning true once the duration has run out
+    pub fn tick_turn(&mut self) -> bool {
+        if let Duration::Turns(x) = self {
+            *x -= 1;
+            return *x <= 0;
+        }
+        false
+    }
+
+    //Counts down a round, returning true once the duration has run out
+    pub fn tick_round(&mut self) -> bool {
+        if let Duration::Rounds(x) = self {
+            *x -= 1;
+            return *x <= 0;
+        }
+        false
+    }
+}
+
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Duration::Instant => write!(f, "instant"),
+            Duration::Turns(1) => write!(f, "1 turn"),
+            Duration::Turns(x) => write!(f, "{} turns", x),
+            Duration::Rounds(1) => write!(f, "1 round"),
+            Duration::Rounds(x) => write!(f, "{} rounds", x),
+            Duration::Scene => write!(f, "scene"),
+            Duration::Indefinite => write!(f, "indefinite"),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Charm {
+    pub name: String,
+    #[serde(default)]
+    pub cost: Cost,
+    #[serde(rename = "type")]
+    pub kind: CharmType,
+    #[serde(default)]
+    pub duration: Duration,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub text: String,
+}
+
+//A charm that is still running on a character
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ActiveEffect {
+    pub name: String,
+    pub duration: Duration,
+}
+
+impl ActiveEffect {
+    pub fn tick_turn(&mut self) -> bool {
+        self.duration.tick_turn()
+    }
+
+    pub fn tick_round(&mut self) -> bool {
+        self.duration.tick_round()
+    }
+}
+
+impl fmt::Display for ActiveEffect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.duration)
+    }
+}
+
+//Charms anyone can activate, read from charms.json if there is one
+pub struct CharmLibrary {
+    charms: Vec<Charm>,
+}
+
+impl CharmLibrary {
+    pub fn load() -> CharmLibrary {
+        let charms = match std::fs::read_to_string("charms.json") {
+            Ok(x) => serde_json::from_str(x.as_str()).expect("charms.json has invalid formatting"),
+            Err(_) => Vec::new(),
+        };
+        CharmLibrary { charms }
+    }
+
+    pub fn charms(&self) -> &[Charm] {
+        &self.charms
+    }
+}
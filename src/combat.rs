@@ -1,11 +1,330 @@
-use crate::util::roll_dice;
+use crate::battlegroup::BattleGroup;
+use crate::charms::{ActiveEffect, Charm, CharmType, Duration};
+use crate::condition::{Condition, ConditionSource};
+use crate::dice::{DicePool, RollResult};
+use crate::essence::{Anima, Pool, Resource};
+use crate::event::{deserialize_log, CharRef, CombatEvent};
+use crate::gambit::Gambit;
+use crate::grapple::{Grapple, GrappleAction};
+use crate::range::{Movement, Range, RangeBand};
+use crate::rules;
+use crate::rules::INITIATIVE_BREAK_BONUS;
+use crate::summary::CombatSummary;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct Attack {
     pub name: String,
     pub dice: i32,
     pub damage: String,
+    #[serde(default)]
+    pub damage_type: Option<DamageType>,
+    //Farthest range the attack reaches, melee attacks leave this out
+    #[serde(default)]
+    pub range: Option<Range>,
+}
+
+//Withering damage after soak never drops below this many dice
+pub const MIN_WITHERING_DAMAGE: i32 = 1;
+
+impl Attack {
+    pub fn new(name: &str, dice: i32, damage: &str) -> Attack {
+        Attack {
+            name: name.to_string(),
+            dice,
+            damage: damage.to_string(),
+            ..Default::default()
+        }
+    }
+
+    //Base damage dice, read from the front of the damage text (e.g. "12L")
+    pub fn damage_dice(&self) -> i32 {
+        let digits: String =
+            self.damage.trim().chars().take_while(|x| x.is_ascii_digit()).collect();
+        digits.parse::<i32>().unwrap_or(0)
+    }
+
+    //Explicit damage type, or the one written after the damage (e.g. "12L")
+    pub fn damage_type(&self) -> DamageType {
+        self.damage_type
+            .or_else(|| DamageType::from_suffix(&self.damage))
+            .unwrap_or(DamageType::Lethal)
+    }
+
+    pub fn max_range(&self) -> Range {
+        self.range.unwrap_or(Range::Close)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct AttackRoll {
+    pub accuracy: RollResult,
+    pub defense: i32,
+    pub damage: Option<RollResult>,
+}
+
+impl AttackRoll {
+    pub fn hit(&self) -> bool {
+        self.damage.is_some()
+    }
+
+    //Rolled damage successes, -1 for a miss
+    pub fn damage(&self) -> i32 {
+        match &self.damage {
+            Some(x) => x.successes,
+            None => -1,
+        }
+    }
+}
+
+impl fmt::Display for AttackRoll {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Acc {} vs {}", self.accuracy, self.defense)?;
+        if let Some(damage) = &self.damage {
+            write!(f, ", Dmg {}", damage)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageType {
+    Bashing,
+    Lethal,
+    Aggravated,
+}
+
+impl DamageType {
+    pub fn from_suffix(text: &str) -> Option<DamageType> {
+        match text.trim().chars().last()?.to_ascii_uppercase() {
+            'B' => Some(DamageType::Bashing),
+            'L' => Some(DamageType::Lethal),
+            'A' => Some(DamageType::Aggravated),
+            _ => None,
+        }
+    }
+
+    pub fn suffix(&self) -> char {
+        match self {
+            DamageType::Bashing => 'B',
+            DamageType::Lethal => 'L',
+            DamageType::Aggravated => 'A',
+        }
+    }
+}
+
+//A health track of wound penalty levels, followed by a single Incapacitated level.
+//Damage fills the track from the left, most severe damage type first.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(from = "HealthRecord", into = "HealthRecord")]
+pub struct HealthTrack {
+    penalties: Vec<i32>,
+    bashing: i32,
+    lethal: i32,
+    aggravated: i32,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum HealthLevels {
+    Count(i32),
+    Penalties(Vec<i32>),
+}
+
+#[derive(Deserialize, Serialize, Default)]
+struct HealthDamage {
+    #[serde(default)]
+    bashing: i32,
+    #[serde(default)]
+    lethal: i32,
+    #[serde(default)]
+    aggravated: i32,
+}
+
+//On-disk form of a health track, also accepting the older health/current_health pair
+#[derive(Deserialize, Serialize)]
+struct HealthRecord {
+    health: HealthLevels,
+    #[serde(default, skip_serializing)]
+    current_health: Option<i32>,
+    #[serde(default)]
+    damage: Option<HealthDamage>,
+}
+
+impl From<HealthRecord> for HealthTrack {
+    fn from(record: HealthRecord) -> HealthTrack {
+        let mut track = match record.health {
+            HealthLevels::Count(x) => HealthTrack::new(x),
+            HealthLevels::Penalties(x) => HealthTrack::from_penalties(x),
+        };
+        match (record.damage, record.current_health) {
+            (Some(damage), _) => {
+                track.take(DamageType::Aggravated, damage.aggravated);
+                track.take(DamageType::Lethal, damage.lethal);
+                track.take(DamageType::Bashing, damage.bashing);
+            }
+            (None, Some(current)) => {
+                track.take(DamageType::Lethal, track.levels() - current);
+            }
+            (None, None) => {}
+        }
+        track
+    }
+}
+
+impl From<HealthTrack> for HealthRecord {
+    fn from(track: HealthTrack) -> HealthRecord {
+        HealthRecord {
+            health: HealthLevels::Penalties(track.penalties),
+            current_health: None,
+            damage: Some(HealthDamage {
+                bashing: track.bashing,
+                lethal: track.lethal,
+                aggravated: track.aggravated,
+            }),
+        }
+    }
+}
+
+impl HealthTrack {
+    //The standard -0, -1, -1, -2, -2, -4, Incapacitated track has 7 levels
+    pub fn new(levels: i32) -> HealthTrack {
+        let wounds = std::cmp::max(levels - 1, 0);
+        let mut penalties = Vec::new();
+        if wounds > 0 {
+            penalties.push(0);
+        }
+        for idx in 0..wounds - 2 {
+            penalties.push(if idx < (wounds - 1) / 2 { -1 } else { -2 });
+        }
+        if wounds > 1 {
+            penalties.push(-4);
+        }
+        HealthTrack::from_penalties(penalties)
+    }
+
+    pub fn from_penalties(penalties: Vec<i32>) -> HealthTrack {
+        HealthTrack {
+            penalties,
+            bashing: 0,
+            lethal: 0,
+            aggravated: 0,
+        }
+    }
+
+    pub fn levels(&self) -> i32 {
+        self.penalties.len() as i32 + 1
+    }
+
+    pub fn damage(&self) -> i32 {
+        self.bashing + self.lethal + self.aggravated
+    }
+
+    pub fn remaining(&self) -> i32 {
+        self.levels() - self.damage()
+    }
+
+    pub fn damage_of(&self, kind: DamageType) -> i32 {
+        match kind {
+            DamageType::Bashing => self.bashing,
+            DamageType::Lethal => self.lethal,
+            DamageType::Aggravated => self.aggravated,
+        }
+    }
+
+    fn damage_mut(&mut self, kind: DamageType) -> &mut i32 {
+        match kind {
+            DamageType::Bashing => &mut self.bashing,
+            DamageType::Lethal => &mut self.lethal,
+            DamageType::Aggravated => &mut self.aggravated,
+        }
+    }
+
+    //Marks amount levels of damage, returns the number of levels that changed.
+    //On a full track, damage upgrades bashing levels, and aggravated damage also
+    //upgrades lethal levels.
+    pub fn take(&mut self, kind: DamageType, amount: i32) -> i32 {
+        let mut taken = 0;
+        for _ in 0..amount {
+            if self.remaining() > 0 {
+                *self.damage_mut(kind) += 1;
+            } else if self.bashing > 0 {
+                self.bashing -= 1;
+                match kind {
+                    DamageType::Aggravated => self.aggravated += 1,
+                    _ => self.lethal += 1,
+                }
+            } else if kind == DamageType::Aggravated && self.lethal > 0 {
+                self.lethal -= 1;
+                self.aggravated += 1;
+            } else {
+                break;
+            }
+            taken += 1;
+        }
+        taken
+    }
+
+    pub fn heal(&mut self, kind: DamageType, amount: i32) {
+        let damage = self.damage_mut(kind);
+        *damage = std::cmp::max(*damage - std::cmp::max(amount, 0), 0);
+    }
+
+    pub fn heal_all(&mut self) {
+        self.bashing = 0;
+        self.lethal = 0;
+        self.aggravated = 0;
+    }
+
+    //Wound penalty of a level, None for the Incapacitated level
+    pub fn penalty(&self, level: i32) -> Option<i32> {
+        self.penalties.get(level as usize).copied()
+    }
+
+    //Damage type marked in a level, if any
+    pub fn level(&self, level: i32) -> Option<DamageType> {
+        if level < self.aggravated {
+            Some(DamageType::Aggravated)
+        } else if level < self.aggravated + self.lethal {
+            Some(DamageType::Lethal)
+        } else if level < self.damage() {
+            Some(DamageType::Bashing)
+        } else {
+            None
+        }
+    }
+
+    pub fn wound_penalty(&self) -> i32 {
+        if self.damage() == 0 {
+            return 0;
+        }
+        let worst = std::cmp::min(self.damage(), self.penalties.len() as i32) - 1;
+        self.penalty(worst).unwrap_or(0)
+    }
+
+    pub fn incapacitated(&self) -> bool {
+        self.remaining() <= 0
+    }
+
+    //A track full of bashing damage only knocks a character out
+    pub fn dead(&self) -> bool {
+        self.incapacitated() && self.bashing == 0
+    }
+}
+
+impl fmt::Display for HealthTrack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.remaining(), self.levels())?;
+        for kind in [DamageType::Bashing, DamageType::Lethal, DamageType::Aggravated] {
+            if self.damage_of(kind) > 0 {
+                write!(f, " {}{}", self.damage_of(kind), kind.suffix())?;
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -14,54 +333,138 @@ pub struct Special {
     pub text: String,
 }
 
+//Who a character fights for, characters from characters.json are players and monsters enemies
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Default)]
+pub enum Side {
+    Players,
+    Enemies,
+    #[default]
+    Neutral,
+    Custom(String),
+}
+
+impl Side {
+    //Neutral characters are nobody's enemy
+    pub fn hostile_to(&self, other: &Side) -> bool {
+        self != other && *self != Side::Neutral && *other != Side::Neutral
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Side::Players => write!(f, "Players"),
+            Side::Enemies => write!(f, "Enemies"),
+            Side::Neutral => write!(f, "Neutral"),
+            Side::Custom(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+//Identifies a character within an encounter, 0 until the encounter assigns one
+pub type CharId = u32;
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Character {
+    #[serde(default)]
+    pub id: CharId,
     pub name: String,
     #[serde(default = "Character::default_label")]
     pub label: Option<char>,
+    #[serde(default)]
+    pub side: Side,
     #[serde(default = "Character::default_zero")]
     pub initiative: i32,
     #[serde(default = "Character::default_zero")]
     pub crashed_turns: i32,
-    pub crasher_name: Option<String>, 
+    #[serde(default)]
+    pub crasher: Option<CharId>,
+    //Older saves named the crasher instead, update turns the name into an id
+    #[serde(default, skip_serializing)]
+    crasher_name: Option<String>,
     pub joinbattle: i32,
     #[serde(default = "Character::default_zero")]
     pub onslaught: i32,
     #[serde(default = "Character::default_false")]
     pub done: bool,
-    #[serde(rename = "health")]
-    pub maxhealth: i32,
+    #[serde(default = "Character::default_false")]
+    pub extra_turn: bool,
+    #[serde(default = "Character::default_false")]
+    pub delayed: bool,
     #[serde(default = "Character::default_zero")]
-    #[serde(rename = "current_health")]
-    pub health: i32,
+    pub jb_successes: i32,
+    #[serde(flatten)]
+    pub health: HealthTrack,
     pub evasion: i32,
     pub parry: i32,
+    #[serde(default = "Character::default_zero")]
+    pub defense_modifier: i32,
     pub soak: i32,
     #[serde(default = "Character::default_zero")]
     pub hardness: i32,
     pub attacks: Option<Vec<Attack>>,
     pub specials: Option<Vec<Special>>,
+    #[serde(default)]
+    pub battle_group: Option<BattleGroup>,
+    #[serde(default = "Character::default_zero")]
+    pub essence: i32,
+    #[serde(default)]
+    pub personal: Pool,
+    #[serde(default)]
+    pub peripheral: Pool,
+    #[serde(default)]
+    pub willpower: Pool,
+    #[serde(default)]
+    pub anima: Anima,
+    #[serde(default = "Character::default_zero")]
+    pub anima_motes: i32,
+    #[serde(default)]
+    pub charms: Option<Vec<Charm>>,
+    #[serde(default)]
+    pub effects: Vec<ActiveEffect>,
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    //Conditions that ran out since the encounter last logged them
+    #[serde(skip)]
+    pub expired: Vec<Condition>,
 }
 
 impl Character {
     pub fn new(name: String, joinbattle: i32, maxhealth: i32) -> Character {
         Character {
+            id: 0,
             name,
             label: None,
-            maxhealth,
-            health: maxhealth,
+            side: Side::Neutral,
+            health: HealthTrack::new(maxhealth),
             joinbattle,
             initiative: 0,
             crashed_turns: 0, 
-            crasher_name: None, 
+            crasher: None,
+            crasher_name: None,
             onslaught: 0,
             done: false,
+            extra_turn: false,
+            delayed: false,
+            jb_successes: 0,
             evasion: 0,
             parry: 0,
+            defense_modifier: 0,
             soak: 0,
             hardness: 0,
             attacks: None,
             specials: None,
+            battle_group: None,
+            essence: 0,
+            personal: Pool::default(),
+            peripheral: Pool::default(),
+            willpower: Pool::default(),
+            anima: Anima::Dim,
+            anima_motes: 0,
+            charms: None,
+            effects: Vec::new(),
+            conditions: Vec::new(),
+            expired: Vec::new(),
         }
     }
     fn default_label() -> Option<char> { None }
@@ -69,72 +472,152 @@ impl Character {
     fn default_false() -> bool { false }
 
     pub fn load_characters() -> Vec<Character> {
-        let mut char_list: Vec<Character> = serde_json::from_str(
+        let char_list: Vec<Character> = Character::parse_with_side(
             std::fs::read_to_string("characters.json")
                 .expect("Could not open characters.json")
                 .as_str(),
+            Side::Players,
         )
         .expect("characters.json has invalid formatting");
-        for char in char_list.iter_mut() {
-            char.reset();
-        }
         char_list
     }
     pub fn load_monsters() -> Vec<Character> {
-        let monster_list: Vec<Character> = serde_json::from_str(
+        let monster_list: Vec<Character> = Character::parse_with_side(
             std::fs::read_to_string("monsters.json")
                 .expect("Could not open monsters.json")
                 .as_str(),
+            Side::Enemies,
         )
         .expect("monsters.json has invalid formatting");
         monster_list
     }
-    pub fn reset(&mut self) {
-        self.initiative = roll_dice(self.joinbattle) + 3;
-        if self.initiative < 0 {
-            self.initiative = 0;
+    //Reads a list of characters, putting those without a side on the given one
+    pub fn parse_with_side(text: &str, side: Side) -> serde_json::Result<Vec<Character>> {
+        let mut values: Vec<serde_json::Value> = serde_json::from_str(text)?;
+        for value in values.iter_mut() {
+            if let Some(x) = value.as_object_mut() {
+                x.entry("side").or_insert(serde_json::to_value(&side)?);
+            }
+        }
+        serde_json::from_value(serde_json::Value::Array(values))
+    }
+    pub fn reset<R: Rng + ?Sized>(&mut self, rng: &mut R) -> RollResult {
+        let roll = DicePool::new(self.joinbattle).roll(rng);
+        self.initiative = rules::base_initiative(&roll);
+        self.jb_successes = roll.successes;
+        self.health.heal_all();
+        self.effects.clear();
+        self.conditions.clear();
+        let levels = self.health.levels();
+        if let Some(x) = &mut self.battle_group {
+            x.restore(levels);
         }
-        self.health = self.maxhealth;
+        roll
     }
     pub fn finish(&mut self) {
-        if self.crashed() && self.crashed_turns < 2 {
+        if self.crashed() && !rules::crash_recovers(self.crashed_turns) {
             self.crashed_turns += 1;
-        } else if self.crashed() && self.crashed_turns >= 2 {
-            self.initiative = 3;
+        } else if self.crashed() {
+            self.initiative = rules::CRASH_RECOVERY_INITIATIVE;
             self.crashed_turns = 0;
+            self.crasher = None;
         }
         self.done = true;
+        self.extra_turn = false;
+        self.delayed = false;
         self.onslaught = 0;
+        self.effects.retain_mut(|x| !x.tick_turn());
+        self.tick_conditions(Duration::tick_turn);
+    }
+    //Counts conditions down, keeping the ones that run out to be logged
+    pub fn tick_conditions(&mut self, tick: fn(&mut Duration) -> bool) {
+        for mut condition in std::mem::take(&mut self.conditions) {
+            match tick(&mut condition.duration) {
+                true => self.expired.push(condition),
+                false => self.conditions.push(condition),
+            }
+        }
     }
     pub fn crashed(&self) -> bool {
-        self.initiative < 0
+        rules::is_crashed(self.initiative)
     }
     pub fn dead(&self) -> bool {
-        self.health <= 0
+        match &self.battle_group {
+            Some(x) => x.size <= 0,
+            None => self.health.dead(),
+        }
+    }
+    pub fn incapacitated(&self) -> bool {
+        match &self.battle_group {
+            Some(x) => x.incapacitated(),
+            None => self.health.incapacitated(),
+        }
+    }
+    //Battle groups shrug off wounds, they lose Magnitude instead
+    pub fn wound_penalty(&self) -> i32 {
+        match &self.battle_group {
+            Some(_) => 0,
+            None => self.health.wound_penalty(),
+        }
+    }
+    //Health track, or Magnitude and Size for battle groups
+    pub fn health_status(&self) -> String {
+        match &self.battle_group {
+            Some(x) => x.to_string(),
+            None => self.health.to_string(),
+        }
+    }
+    pub fn display_name(&self) -> String {
+        match self.label {
+            Some(x) => format!("{} {}", self.name, x),
+            None => self.name.clone(),
+        }
+    }
+    pub fn char_ref(&self) -> CharRef {
+        CharRef {
+            id: self.id,
+            name: self.display_name(),
+        }
     }
-    pub fn sortkey(&self) -> i32 {
+    //Turn order, ties go to the better Join Battle roll and then the larger pool
+    pub fn sortkey(&self) -> (i32, i32, i32) {
         let mut key = -self.initiative;
-        if self.health <= 0 {
+        if self.extra_turn {
+            key -= 10000;
+        }
+        if self.incapacitated() {
             key += 5000;
         }
         if self.done {
             key += 1000;
         }
-        key
+        (key, -self.jb_successes, -self.joinbattle)
     }
     pub fn ready(&mut self) {
         self.done = false;
+        self.delayed = false;
+    }
+
+    //Characters who have not acted yet this round and are not holding their action
+    pub fn can_act(&self) -> bool {
+        !self.done && !self.delayed && !self.incapacitated()
     }
-    pub fn take_withering_hit(&mut self, attacker_name: String, damage: i32) -> bool {
+    pub fn take_withering_hit(&mut self, attacker: CharId, damage: i32) -> bool {
+        //Withering damage cuts straight into a battle group's Magnitude
+        let levels = self.health.levels();
+        if let Some(x) = &mut self.battle_group {
+            x.take(levels, damage);
+            return false;
+        }
         let mut crashed = false;
         if damage >= 0 {
-            crashed = self.crashed();
+            let before = self.initiative;
             self.initiative -= damage;
             self.onslaught -= 1;
-            crashed = self.crashed() && !crashed;
+            crashed = rules::crashes(before, self.initiative);
         }
         if crashed {
-            self.crasher_name = Some(attacker_name);
+            self.crasher = Some(attacker);
         }
         crashed
     }
@@ -143,25 +626,153 @@ impl Character {
         if damage < 0 {
             self.initiative += 1;
         } else {
-            self.initiative += damage + 1;
-            if crashed {
-                self.initiative += 5;
-            }
+            self.initiative += damage + 1 + rules::initiative_break(crashed);
         }
         if wascrashed && !self.crashed() {
-            self.crasher_name = None;
+            self.crasher = None;
         }
         self.finish();
     }
+    //Resets initiative from a fresh Join Battle roll and grants an immediate turn
+    pub fn initiative_shift<R: Rng + ?Sized>(&mut self, rng: &mut R) -> RollResult {
+        let roll = DicePool::new(self.joinbattle).roll(rng);
+        self.initiative = rules::shift_initiative(self.initiative, &roll);
+        self.jb_successes = roll.successes;
+        self.crashed_turns = 0;
+        self.done = false;
+        self.extra_turn = true;
+        roll
+    }
+    pub fn pool(&self, resource: Resource) -> &Pool {
+        match resource {
+            Resource::Personal => &self.personal,
+            Resource::Peripheral => &self.peripheral,
+            Resource::Willpower => &self.willpower,
+        }
+    }
+    pub fn pool_mut(&mut self, resource: Resource) -> &mut Pool {
+        match resource {
+            Resource::Personal => &mut self.personal,
+            Resource::Peripheral => &mut self.peripheral,
+            Resource::Willpower => &mut self.willpower,
+        }
+    }
+    //Motes regained at the start of a round fill the peripheral pool first
+    pub fn respire(&mut self, motes: i32) {
+        let left = self.peripheral.regain(motes);
+        self.personal.regain(left);
+    }
+    //Raises the anima banner for peripheral motes spent at once
+    pub fn flare(&mut self, motes: i32) {
+        self.anima = self.anima.rise(rules::anima_levels(motes));
+        self.anima_motes += motes;
+    }
+    //The banner fades a level over any round without peripheral motes spent
+    pub fn fade_anima(&mut self) {
+        if self.anima_motes == 0 {
+            self.anima = self.anima.fall();
+        }
+        self.anima_motes = 0;
+    }
+    //Penalties and bonuses shared by evasion and parry, battle groups ignore onslaught
+    pub fn defense_penalty(&self) -> i32 {
+        let conditions: i32 = self.conditions.iter().map(|x| x.defense).sum();
+        match &self.battle_group {
+            Some(x) => x.drill.defense_bonus() + self.defense_modifier + conditions,
+            None => self.onslaught + self.wound_penalty() + self.defense_modifier + conditions,
+        }
+    }
+    //Puts on a condition, replacing the same one from the same source
+    pub fn apply_condition(&mut self, condition: Condition) {
+        self.conditions.retain(|x| !x.same_as(&condition));
+        self.conditions.push(condition);
+    }
+
+    //The farthest any of the character's attacks reaches, close range without any
+    pub fn reach(&self) -> Range {
+        let attacks = self.attacks.iter().flatten();
+        attacks.map(|x| x.max_range()).max().unwrap_or(Range::Close)
+    }
+
+    //Penalties to attack dice pools from wounds and conditions
+    pub fn pool_penalty(&self) -> i32 {
+        self.wound_penalty() + self.conditions.iter().map(|x| x.pools).sum::<i32>()
+    }
+    pub fn effective_soak(&self) -> i32 {
+        self.soak + self.battle_group.as_ref().map_or(0, |x| x.size)
+    }
+    //Extra accuracy and damage dice from being a battle group
+    pub fn attack_bonus(&self) -> i32 {
+        self.battle_group.as_ref().map_or(0, |x| x.attack_bonus())
+    }
+    pub fn effective_evasion(&self) -> i32 {
+        std::cmp::max(self.evasion + self.defense_penalty(), 0)
+    }
+    pub fn effective_parry(&self) -> i32 {
+        std::cmp::max(self.parry + self.defense_penalty(), 0)
+    }
+    pub fn effective_defense(&self) -> i32 {
+        std::cmp::max(self.effective_evasion(), self.effective_parry())
+    }
+    pub fn roll_withering<R: Rng + ?Sized>(
+        &self, attack: &Attack, defender: &Character, rng: &mut R,
+    ) -> AttackRoll {
+        let accuracy =
+            DicePool::new(attack.dice + self.pool_penalty() + self.attack_bonus()).roll(rng);
+        let defense = defender.effective_defense();
+        let threshold = accuracy.successes - defense;
+        let damage = if accuracy.botch || threshold < 0 {
+            None
+        } else {
+            let pool = attack.damage_dice() + self.attack_bonus() + threshold
+                - defender.effective_soak();
+            Some(DicePool::damage(std::cmp::max(pool, MIN_WITHERING_DAMAGE)).roll(rng))
+        };
+        AttackRoll {
+            accuracy,
+            defense,
+            damage,
+        }
+    }
+    pub fn roll_decisive<R: Rng + ?Sized>(
+        &self, attack: &Attack, defender: &Character, rng: &mut R,
+    ) -> AttackRoll {
+        let accuracy =
+            DicePool::new(attack.dice + self.pool_penalty() + self.attack_bonus()).roll(rng);
+        let defense = defender.effective_defense();
+        let damage = if accuracy.botch || accuracy.successes < defense {
+            None
+        } else {
+            Some(DicePool::damage(self.initiative + self.attack_bonus()).roll(rng))
+        };
+        AttackRoll {
+            accuracy,
+            defense,
+            damage,
+        }
+    }
     pub fn hardness(&self) -> i32 {
         match self.crashed() {
             true => 0, 
             false => self.hardness
         }
     }
-    pub fn take_decisive_hit(&mut self, damage: i32) {
-        if damage > self.hardness() {
-            self.health -= damage;
+    pub fn take_decisive_hit(&mut self, damage: i32, kind: DamageType) -> i32 {
+        if !self.penetrates(damage) {
+            return 0;
+        }
+        self.take_decisive_damage(damage, kind)
+    }
+    //Hardness stops decisive attacks whose raw damage does not exceed it
+    pub fn penetrates(&self, raw_damage: i32) -> bool {
+        raw_damage > self.hardness()
+    }
+    //Marks decisive damage that already got past hardness
+    pub fn take_decisive_damage(&mut self, damage: i32, kind: DamageType) -> i32 {
+        let levels = self.health.levels();
+        match &mut self.battle_group {
+            Some(x) => x.take(levels, damage),
+            None => self.health.take(kind, damage),
         }
     }
     pub fn do_decisive_hit(&mut self) {
@@ -178,10 +789,141 @@ impl Character {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum CombatError {
+    NoSuchCharacter,
+    NoSuchAttack,
+    SameCharacter,
+    LastCharacter,
+    Incapacitated(String),
+    Crashed(String),
+    Permanent(String),
+    NoInitiative(String),
+    NoCondition(String),
+    NoEffect(String),
+    Grappling(String),
+    NotGrappling(String),
+    NotHeld(String),
+    ControlSpent(String),
+    NotSavage,
+    OutOfRange(String, Range),
+    MustDisengage(String),
+    CannotDelay(String),
+    NotDelayed(String),
+    NotEnough(String, Resource),
+    NotEnoughMotes(String),
+    NoAmount,
+}
+
+impl fmt::Display for CombatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CombatError::NoSuchCharacter => write!(f, "No such character"),
+            CombatError::NoSuchAttack => write!(f, "No such attack"),
+            CombatError::SameCharacter => write!(f, "Character cannot attack itself"),
+            CombatError::LastCharacter => write!(f, "Cannot remove last character"),
+            CombatError::Incapacitated(x) => write!(f, "{} is incapacitated", x),
+            CombatError::Crashed(x) => write!(f, "Crashed {} cannot decisive attack", x),
+            CombatError::Permanent(x) => write!(f, "{} is permanent and cannot be activated", x),
+            CombatError::NoInitiative(x) => write!(f, "{} does not have enough initiative", x),
+            CombatError::NoCondition(x) => write!(f, "No condition {}", x),
+            CombatError::NoEffect(x) => write!(f, "No active effect {}", x),
+            CombatError::Grappling(x) => write!(f, "{} is caught up in a grapple", x),
+            CombatError::NotGrappling(x) => write!(f, "{} is not grappling", x),
+            CombatError::NotHeld(x) => write!(f, "{} is not held in a grapple", x),
+            CombatError::ControlSpent(x) => write!(f, "{} already used control this round", x),
+            CombatError::NotSavage => write!(f, "Savaging needs damage"),
+            CombatError::OutOfRange(x, y) => write!(f, "{} is at {} range", x, y),
+            CombatError::MustDisengage(x) => write!(f, "{} must disengage to leave close range", x),
+            CombatError::CannotDelay(x) => write!(f, "{} has no action to delay", x),
+            CombatError::NotDelayed(x) => write!(f, "{} is not delaying", x),
+            CombatError::NotEnough(x, y) => write!(f, "{} does not have enough {}", x, y),
+            CombatError::NotEnoughMotes(x) => write!(f, "{} does not have enough motes", x),
+            CombatError::NoAmount => write!(f, "Nothing to spend or regain"),
+        }
+    }
+}
+
+impl std::error::Error for CombatError {}
+
+//Result of an attack, damage is initiative for withering and health levels for decisive
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub hit: bool,
+    pub damage: i32,
+    pub crashed: bool,
+    pub shifted: bool,
+    pub roll: Option<AttackRoll>,
+}
+
+//Character values that can be set by hand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stat {
+    Initiative,
+    Onslaught,
+    DefenseModifier,
+}
+
+impl Stat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stat::Initiative => "initiative",
+            Stat::Onslaught => "onslaught",
+            Stat::DefenseModifier => "defense modifier",
+        }
+    }
+
+    fn value_mut<'a>(&self, char: &'a mut Character) -> &'a mut i32 {
+        match self {
+            Stat::Initiative => &mut char.initiative,
+            Stat::Onslaught => &mut char.onslaught,
+            Stat::DefenseModifier => &mut char.defense_modifier,
+        }
+    }
+}
+
+//Number of actions that can be undone
+pub const HISTORY_LIMIT: usize = 100;
+
+//Encounter state before an action, log_len points at the first log line of that action
+#[derive(Debug, Clone)]
+struct Snapshot {
+    characters: Vec<Character>,
+    grapples: Vec<Grapple>,
+    ranges: Vec<RangeBand>,
+    round: u32,
+    current: Option<CharId>,
+    over: bool,
+    //Undoing a rolled action and taking it again rolls the same dice
+    rng: ChaCha8Rng,
+    log_len: usize,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Encounter {
     characters: Vec<Character>,
-    log: Vec<String>, 
+    #[serde(default = "Encounter::default_next_id")]
+    next_id: CharId,
+    #[serde(default = "Encounter::default_round")]
+    round: u32,
+    #[serde(default)]
+    current: Option<CharId>,
+    #[serde(default)]
+    over: bool,
+    #[serde(default)]
+    grapples: Vec<Grapple>,
+    #[serde(default)]
+    ranges: Vec<RangeBand>,
+    #[serde(deserialize_with = "deserialize_log")]
+    log: Vec<CombatEvent>,
+    #[serde(default)]
+    reverted: Vec<(usize, usize)>,
+    #[serde(default = "Encounter::default_rng")]
+    rng: ChaCha8Rng,
+    #[serde(skip)]
+    history: Vec<Snapshot>,
+    #[serde(skip)]
+    future: Vec<Snapshot>,
 }
 
 impl Default for Encounter {
@@ -192,20 +934,141 @@ impl Default for Encounter {
 
 impl Encounter {
     pub fn new() -> Encounter {
-        Encounter {
-            characters: Character::load_characters(),
+        Encounter::with_seed(rand::random())
+    }
+
+    pub fn with_seed(seed: u64) -> Encounter {
+        Encounter::from_characters(Character::load_characters(), seed)
+    }
+
+    pub fn from_characters(characters: Vec<Character>, seed: u64) -> Encounter {
+        let mut encounter = Encounter {
+            characters: Vec::new(),
+            next_id: Encounter::default_next_id(),
+            round: Encounter::default_round(),
+            current: None,
+            over: false,
+            grapples: Vec::new(),
+            ranges: Vec::new(),
             log: Vec::new(),
+            reverted: Vec::new(),
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            history: Vec::new(),
+            future: Vec::new(),
+        };
+        encounter.join_battle(characters);
+        encounter
+    }
+
+    fn default_rng() -> ChaCha8Rng { ChaCha8Rng::from_entropy() }
+    fn default_next_id() -> CharId { 1 }
+    fn default_round() -> u32 { 1 }
+
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+    }
+
+    fn join_battle(&mut self, characters: Vec<Character>) {
+        for char in characters {
+            self.enter(char);
+        }
+    }
+
+    //Adds a character and logs their Join Battle roll
+    fn enter(&mut self, char: Character) -> CharId {
+        let id = self.next_id;
+        let roll = self.add_char(char);
+        self.log(CombatEvent::CharacterJoined {
+            character: self.char_by_id(id).unwrap().char_ref(),
+            roll: Some(roll),
+        });
+        self.check_over();
+        id
+    }
+
+    pub fn log(&mut self, event: CombatEvent) {
+        self.log.push(event);
+    }
+
+    fn snapshot(&self, log_len: usize) -> Snapshot {
+        Snapshot {
+            characters: self.characters.clone(),
+            grapples: self.grapples.clone(),
+            ranges: self.ranges.clone(),
+            round: self.round,
+            current: self.current,
+            over: self.over,
+            rng: self.rng.clone(),
+            log_len,
         }
     }
 
-    pub fn log(&mut self, message: String) {
-        self.log.push(message);
+    //Remembers the current state, call before every action that should be undoable
+    pub fn checkpoint(&mut self) {
+        self.history.push(self.snapshot(self.log.len()));
+        if self.history.len() > HISTORY_LIMIT {
+            self.history.remove(0);
+        }
+        self.future.clear();
     }
 
-    pub fn log_iter(&self) -> impl Iterator<Item = &String> {
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.characters = snapshot.characters;
+        self.grapples = snapshot.grapples;
+        self.ranges = snapshot.ranges;
+        self.round = snapshot.round;
+        self.current = snapshot.current;
+        self.over = snapshot.over;
+        self.rng = snapshot.rng;
+        self.update();
+    }
+
+    fn undone_event(&self, snapshot: &Snapshot) -> Option<Box<CombatEvent>> {
+        self.log.get(snapshot.log_len).cloned().map(Box::new)
+    }
+
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some(x) => {
+                self.future.push(self.snapshot(x.log_len));
+                self.reverted.push((x.log_len, self.log.len()));
+                self.log(CombatEvent::Undo(self.undone_event(&x)));
+                self.restore(x);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.future.pop() {
+            Some(x) => {
+                self.history.push(self.snapshot(x.log_len));
+                if let Some(i) = self.reverted.iter().rposition(|y| y.0 == x.log_len) {
+                    self.reverted.remove(i);
+                }
+                self.log(CombatEvent::Redo(self.undone_event(&x)));
+                self.restore(x);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn log_iter(&self) -> impl Iterator<Item = &CombatEvent> {
         self.log.iter()
     }
 
+    //The log without undone events or the undo and redo entries themselves
+    pub fn active_log(&self) -> impl Iterator<Item = &CombatEvent> {
+        self.log
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !self.reverted.iter().any(|x| (x.0..x.1).contains(i)))
+            .map(|(_, x)| x)
+            .filter(|x| !matches!(x, CombatEvent::Undo(_) | CombatEvent::Redo(_)))
+    }
+
     pub fn log_len(&self) -> usize {
         self.log.len()
     }
@@ -222,40 +1085,1071 @@ impl Encounter {
         self.characters.get(index)
     }
 
-    pub fn char_at_mut(&mut self, index: usize) -> Option<&mut Character> {
-        self.characters.get_mut(index)
+    pub fn char_by_id(&self, id: CharId) -> Option<&Character> {
+        self.characters.iter().find(|x| x.id == id)
+    }
+
+    pub fn char_by_id_mut(&mut self, id: CharId) -> Option<&mut Character> {
+        self.characters.iter_mut().find(|x| x.id == id)
+    }
+
+    //Current position of a character in the sorted character list
+    pub fn position_of(&self, id: CharId) -> Option<usize> {
+        self.characters.iter().position(|x| x.id == id)
+    }
+
+    fn index(&self, id: CharId) -> Result<usize, CombatError> {
+        self.position_of(id).ok_or(CombatError::NoSuchCharacter)
+    }
+
+    fn get_attack(&self, attacker: usize, attack: usize) -> Result<&Attack, CombatError> {
+        match &self.characters[attacker].attacks {
+            Some(x) => x.get(attack).ok_or(CombatError::NoSuchAttack),
+            None => Err(CombatError::NoSuchAttack),
+        }
+    }
+
+    //Looks up both sides of an attack, returning their positions
+    fn check_attack(
+        &self, attacker: CharId, defender: CharId,
+    ) -> Result<(usize, usize), CombatError> {
+        let source = self.index(attacker)?;
+        let target = self.index(defender)?;
+        if attacker == defender {
+            return Err(CombatError::SameCharacter);
+        }
+        if self.characters[source].incapacitated() {
+            return Err(CombatError::Incapacitated(self.characters[source].display_name()));
+        }
+        //Characters in a grapple can only fight each other
+        if self.grapple_of(attacker).is_some_and(|x| x.partner(attacker) != defender) {
+            return Err(CombatError::Grappling(self.characters[source].display_name()));
+        }
+        Ok((source, target))
+    }
+
+    fn check_decisive(
+        &self, attacker: CharId, defender: CharId,
+    ) -> Result<(usize, usize), CombatError> {
+        let (source, target) = self.check_attack(attacker, defender)?;
+        if self.characters[source].crashed() {
+            return Err(CombatError::Crashed(self.characters[source].display_name()));
+        }
+        Ok((source, target))
+    }
+
+    //Applies a withering attack with known damage, None for a miss
+    pub fn withering_attack(
+        &mut self, attacker: CharId, defender: CharId, damage: Option<i32>,
+    ) -> Result<Outcome, CombatError> {
+        let (source, target) = self.check_attack(attacker, defender)?;
+        self.check_range(source, target, self.characters[source].reach())?;
+        self.checkpoint();
+        Ok(self.resolve_withering(source, target, damage, None))
+    }
+
+    //Rolls one of the attacker's attacks as a withering attack
+    pub fn rolled_withering_attack(
+        &mut self, attacker: CharId, attack: usize, defender: CharId,
+    ) -> Result<Outcome, CombatError> {
+        let (source, target) = self.check_attack(attacker, defender)?;
+        let weapon = self.get_attack(source, attack)?.clone();
+        self.check_range(source, target, weapon.max_range())?;
+        self.checkpoint();
+        let roll =
+            self.characters[source].roll_withering(&weapon, &self.characters[target], &mut self.rng);
+        let damage = roll.damage.as_ref().map(|x| x.successes);
+        Ok(self.resolve_withering(source, target, damage, Some(roll)))
+    }
+
+    fn resolve_withering(
+        &mut self, source: usize, target: usize, damage: Option<i32>, roll: Option<AttackRoll>,
+    ) -> Outcome {
+        let attacker = self.characters[source].char_ref();
+        let defender = self.characters[target].char_ref();
+        let size = self.characters[target].battle_group.as_ref().map(|x| x.size);
+        let x = damage.unwrap_or(-1);
+        let crashed = self.characters[target].take_withering_hit(attacker.id, x);
+        let shifted =
+            rules::initiative_shift(&self.characters[source], &self.characters[target], crashed);
+        self.characters[source].do_withering_hit(x, crashed);
+        match damage {
+            Some(damage) => {
+                self.log(CombatEvent::WitheringHit {
+                    attacker: attacker.clone(),
+                    defender: defender.clone(),
+                    damage,
+                    roll: roll.clone(),
+                });
+                if crashed {
+                    self.log(CombatEvent::Crash {
+                        attacker: attacker.clone(),
+                        defender,
+                    });
+                    self.log(CombatEvent::InitiativeBreak {
+                        character: attacker.clone(),
+                        bonus: INITIATIVE_BREAK_BONUS,
+                    });
+                }
+                if shifted {
+                    let roll = self.characters[source].initiative_shift(&mut self.rng);
+                    self.log(CombatEvent::InitiativeShift {
+                        character: attacker,
+                        initiative: self.characters[source].initiative,
+                        roll: Some(roll),
+                    });
+                }
+            }
+            None => {
+                self.log(CombatEvent::WitheringMiss {
+                    attacker,
+                    defender,
+                    roll: roll.clone(),
+                });
+            }
+        }
+        self.check_group(target, size);
+        self.update();
+        self.follow_turn();
+        self.check_over();
+        Outcome {
+            hit: damage.is_some(),
+            damage: std::cmp::max(x, 0),
+            crashed,
+            shifted,
+            roll,
+        }
+    }
+
+    //Applies a decisive attack with known damage, None for a miss
+    pub fn decisive_attack(
+        &mut self, attacker: CharId, defender: CharId, damage: Option<i32>, kind: DamageType,
+    ) -> Result<Outcome, CombatError> {
+        let (source, target) = self.check_decisive(attacker, defender)?;
+        self.check_range(source, target, self.characters[source].reach())?;
+        self.checkpoint();
+        Ok(self.resolve_decisive(source, target, damage, kind, None))
+    }
+
+    //Rolls one of the attacker's attacks as a decisive attack
+    pub fn rolled_decisive_attack(
+        &mut self, attacker: CharId, attack: usize, defender: CharId,
+    ) -> Result<Outcome, CombatError> {
+        let (source, target) = self.check_decisive(attacker, defender)?;
+        let weapon = self.get_attack(source, attack)?.clone();
+        self.check_range(source, target, weapon.max_range())?;
+        let kind = weapon.damage_type();
+        self.checkpoint();
+        let roll =
+            self.characters[source].roll_decisive(&weapon, &self.characters[target], &mut self.rng);
+        let damage = roll.damage.as_ref().map(|x| x.successes);
+        Ok(self.resolve_decisive(source, target, damage, kind, Some(roll)))
+    }
+
+    fn resolve_decisive(
+        &mut self, source: usize, target: usize, damage: Option<i32>, kind: DamageType,
+        roll: Option<AttackRoll>,
+    ) -> Outcome {
+        let attacker = self.characters[source].char_ref();
+        let defender = self.characters[target].char_ref();
+        let size = self.characters[target].battle_group.as_ref().map(|x| x.size);
+        //Rolled attacks check hardness against the initiative rolled, not the successes
+        let char = &self.characters[source];
+        let raw = roll.as_ref().map(|_| char.initiative + char.attack_bonus());
+        let dealt = match damage {
+            Some(x) => {
+                self.characters[source].do_decisive_hit();
+                let dealt = match raw {
+                    Some(raw) if self.characters[target].penetrates(raw) => {
+                        self.characters[target].take_decisive_damage(x, kind)
+                    }
+                    Some(_) => 0,
+                    None => self.characters[target].take_decisive_hit(x, kind),
+                };
+                self.log(CombatEvent::DecisiveHit {
+                    attacker,
+                    defender,
+                    damage: dealt,
+                    kind,
+                    roll: roll.clone(),
+                });
+                dealt
+            }
+            None => {
+                self.characters[source].do_decisive_miss();
+                self.log(CombatEvent::DecisiveMiss {
+                    attacker,
+                    defender,
+                    roll: roll.clone(),
+                });
+                0
+            }
+        };
+        self.check_group(target, size);
+        self.update();
+        self.follow_turn();
+        self.check_over();
+        Outcome {
+            hit: damage.is_some(),
+            damage: dealt,
+            crashed: false,
+            shifted: false,
+            roll,
+        }
+    }
+
+    //Applies a gambit, roll is the successes of the initiative roll and None a missed attack
+    pub fn gambit(
+        &mut self, attacker: CharId, defender: CharId, gambit: Gambit, roll: Option<i32>,
+    ) -> Result<bool, CombatError> {
+        let (source, target) = self.check_gambit(attacker, defender, gambit)?;
+        self.check_range(source, target, self.characters[source].reach())?;
+        self.checkpoint();
+        Ok(self.resolve_gambit(source, target, gambit, roll, None))
+    }
+
+    //Rolls one of the attacker's attacks as a gambit, the damage roll being the initiative roll
+    pub fn rolled_gambit(
+        &mut self, attacker: CharId, attack: usize, defender: CharId, gambit: Gambit,
+    ) -> Result<bool, CombatError> {
+        let (source, target) = self.check_gambit(attacker, defender, gambit)?;
+        let weapon = self.get_attack(source, attack)?.clone();
+        self.check_range(source, target, weapon.max_range())?;
+        self.checkpoint();
+        let roll =
+            self.characters[source].roll_decisive(&weapon, &self.characters[target], &mut self.rng);
+        let successes = roll.damage.as_ref().map(|x| x.successes);
+        Ok(self.resolve_gambit(source, target, gambit, successes, Some(roll)))
+    }
+
+    //A gambit is a decisive attack the attacker must also be able to pay for
+    fn check_gambit(
+        &self, attacker: CharId, defender: CharId, gambit: Gambit,
+    ) -> Result<(usize, usize), CombatError> {
+        let (source, target) = self.check_decisive(attacker, defender)?;
+        if self.characters[source].initiative < rules::gambit_cost(gambit.difficulty()) {
+            return Err(CombatError::NoInitiative(self.characters[source].display_name()));
+        }
+        Ok((source, target))
+    }
+
+    fn resolve_gambit(
+        &mut self, source: usize, target: usize, gambit: Gambit, successes: Option<i32>,
+        roll: Option<AttackRoll>,
+    ) -> bool {
+        let success = self.apply_gambit(source, target, gambit, successes, roll);
+        self.update();
+        self.follow_turn();
+        self.check_over();
+        success
+    }
+
+    //Settles a gambit without ending the turn, so a grapple can take hold first
+    fn apply_gambit(
+        &mut self, source: usize, target: usize, gambit: Gambit, successes: Option<i32>,
+        roll: Option<AttackRoll>,
+    ) -> bool {
+        let attacker = self.characters[source].char_ref();
+        let defender = self.characters[target].char_ref();
+        let success = successes.is_some_and(|x| rules::gambit_succeeds(x, gambit.difficulty()));
+        //A successful gambit pays its cost, then resets like a decisive hit
+        if success {
+            let cost = rules::gambit_cost(gambit.difficulty());
+            self.characters[source].initiative -= cost;
+            self.log(CombatEvent::GambitPaid {
+                character: attacker.clone(),
+                cost,
+            });
+            self.characters[source].do_decisive_hit();
+            if let Some(x) = gambit.condition() {
+                self.characters[target].apply_condition(x);
+            }
+        } else {
+            self.characters[source].do_decisive_miss();
+        }
+        self.log(CombatEvent::Gambit {
+            attacker,
+            defender,
+            gambit,
+            success,
+            roll,
+        });
+        success
+    }
+
+    //Logs a battle group losing Size and has what is left of it check for rout
+    fn check_group(&mut self, index: usize, size: Option<i32>) {
+        let now = match &self.characters[index].battle_group {
+            Some(x) if Some(x.size) < size => x.size,
+            _ => return,
+        };
+        let character = self.characters[index].char_ref();
+        self.log(CombatEvent::SizeLost {
+            character: character.clone(),
+            size: now,
+        });
+        if now > 0 {
+            let group = self.characters[index].battle_group.as_mut().unwrap();
+            let roll = group.rout_check(&mut self.rng);
+            let routed = group.routed;
+            self.log(CombatEvent::RoutCheck {
+                character,
+                roll,
+                routed,
+            });
+        }
+    }
+
+    pub fn toggle_done(&mut self, id: CharId) -> Result<(), CombatError> {
+        let index = self.index(id)?;
+        self.checkpoint();
+        let char = &mut self.characters[index];
+        char.done ^= true;
+        if char.done {
+            char.extra_turn = false;
+        }
+        let event = if char.done {
+            CombatEvent::TurnFinished {
+                character: char.char_ref(),
+            }
+        } else {
+            CombatEvent::ManualEdit {
+                character: char.char_ref(),
+                field: "turn".to_string(),
+                value: "ready".to_string(),
+            }
+        };
+        self.log(event);
+        self.update();
+        self.follow_turn();
+        self.check_over();
+        Ok(())
+    }
+
+    pub fn set_stat(&mut self, id: CharId, stat: Stat, value: i32) -> Result<(), CombatError> {
+        let index = self.index(id)?;
+        self.checkpoint();
+        *stat.value_mut(&mut self.characters[index]) = value;
+        self.log(CombatEvent::ManualEdit {
+            character: self.characters[index].char_ref(),
+            field: stat.name().to_string(),
+            value: value.to_string(),
+        });
+        self.update();
+        Ok(())
+    }
+
+    //Marks damage on a health track by hand, a negative amount heals
+    pub fn change_health(
+        &mut self, id: CharId, amount: i32, kind: DamageType,
+    ) -> Result<(), CombatError> {
+        let index = self.index(id)?;
+        self.checkpoint();
+        let size = self.characters[index].battle_group.as_ref().map(|x| x.size);
+        let char = &mut self.characters[index];
+        let levels = char.health.levels();
+        match &mut char.battle_group {
+            Some(x) if amount < 0 => x.heal(levels, -amount),
+            Some(x) => {
+                x.take(levels, amount);
+            }
+            None if amount < 0 => char.health.heal(kind, -amount),
+            None => {
+                char.health.take(kind, amount);
+            }
+        }
+        self.log(CombatEvent::ManualEdit {
+            character: self.characters[index].char_ref(),
+            field: "health".to_string(),
+            value: self.characters[index].health_status(),
+        });
+        self.check_group(index, size);
+        self.update();
+        self.follow_turn();
+        self.check_over();
+        Ok(())
+    }
+
+    //Spends motes or willpower, a negative amount regains them
+    pub fn spend(
+        &mut self, id: CharId, resource: Resource, amount: i32,
+    ) -> Result<(), CombatError> {
+        let index = self.index(id)?;
+        let char = &self.characters[index];
+        //Nothing to log when the amount is zero or the pool is already full
+        let pool = char.pool(resource);
+        if amount == 0 || (amount < 0 && pool.current >= pool.max) {
+            return Err(CombatError::NoAmount);
+        }
+        if amount > char.pool(resource).current {
+            return Err(CombatError::NotEnough(char.display_name(), resource));
+        }
+        self.checkpoint();
+        let anima = self.characters[index].anima;
+        let char = &mut self.characters[index];
+        let mut amount = amount;
+        //A full pool only takes what fits, and the log shows what was actually gained
+        if amount < 0 {
+            amount += char.pool_mut(resource).regain(-amount);
+        } else {
+            char.pool_mut(resource).spend(amount);
+            if resource == Resource::Peripheral {
+                char.flare(amount);
+            }
+        }
+        self.log(CombatEvent::Spent {
+            character: self.characters[index].char_ref(),
+            resource,
+            amount,
+        });
+        self.log_anima(index, anima);
+        Ok(())
+    }
+
+    //Range band between two characters, close unless they have moved apart
+    pub fn range(&self, a: CharId, b: CharId) -> Range {
+        self.ranges.iter().find(|x| x.between(a, b)).map_or(Range::Close, |x| x.range)
+    }
+
+    fn set_range(&mut self, a: CharId, b: CharId, range: Range) {
+        self.ranges.retain(|x| !x.between(a, b));
+        if range != Range::Close {
+            self.ranges.push(RangeBand::new(a, b, range));
+        }
+    }
+
+    //Refuses attacks made beyond the reach of the weapon, or of the longest attack for attacks
+    //made by hand
+    fn check_range(&self, source: usize, target: usize, reach: Range) -> Result<(), CombatError> {
+        let defender = &self.characters[target];
+        let range = self.range(self.characters[source].id, defender.id);
+        if range > reach {
+            return Err(CombatError::OutOfRange(defender.display_name(), range));
+        }
+        Ok(())
+    }
+
+    //Changes the range between a character and another, rushing and disengaging take their turn
+    pub fn move_char(
+        &mut self, id: CharId, other: CharId, movement: Movement,
+    ) -> Result<Range, CombatError> {
+        let (source, target) = self.check_attack(id, other)?;
+        let range = self.range(id, other);
+        let name = self.characters[source].display_name();
+        let held = self.grapple_of(id).is_some_and(|x| x.partner(id) == other);
+        let (mover, opponent) = (&self.characters[source], &self.characters[target]);
+        //Only an opponent still standing keeps a character from stepping away
+        let hostile = mover.side.hostile_to(&opponent.side) && !opponent.incapacitated();
+        let engaged = range == Range::Close && hostile;
+        let moved = match movement {
+            Movement::Closer => range.closer(),
+            Movement::Farther if engaged => return Err(CombatError::MustDisengage(name)),
+            Movement::Farther => range.farther(),
+            Movement::Rush(true) => Range::Close,
+            Movement::Rush(false) => range.closer(),
+            Movement::Disengage(_) if range != Range::Close => {
+                return Err(CombatError::OutOfRange(self.characters[target].display_name(), range))
+            }
+            Movement::Disengage(true) => Range::Short,
+            Movement::Disengage(false) => Range::Close,
+            Movement::Set(x) => x,
+        };
+        if held && moved != Range::Close {
+            return Err(CombatError::Grappling(name));
+        }
+        self.checkpoint();
+        self.set_range(id, other, moved);
+        self.log(CombatEvent::Moved {
+            character: self.characters[source].char_ref(),
+            other: self.characters[target].char_ref(),
+            movement,
+            range: moved,
+        });
+        if matches!(movement, Movement::Rush(_) | Movement::Disengage(_)) {
+            self.characters[source].finish();
+            self.update();
+            self.follow_turn();
+            self.check_over();
+        }
+        Ok(moved)
+    }
+
+    pub fn grapples(&self) -> &[Grapple] {
+        &self.grapples
+    }
+
+    //The grapple a character is holding or held in
+    pub fn grapple_of(&self, id: CharId) -> Option<&Grapple> {
+        self.grapples.iter().find(|x| x.involves(id))
+    }
+
+    //Resolves a grapple gambit like any other, roll being the initiative roll's successes and
+    //None a missed attack. A grapple that works holds for the rounds of control won, at least one
+    pub fn grapple(
+        &mut self, grappler: CharId, victim: CharId, roll: Option<i32>, control: i32,
+    ) -> Result<bool, CombatError> {
+        let (source, target) = self.check_gambit(grappler, victim, Gambit::Grapple)?;
+        if self.grapple_of(victim).is_some() {
+            return Err(CombatError::Grappling(self.characters[target].display_name()));
+        }
+        let range = self.range(grappler, victim);
+        if range != Range::Close {
+            return Err(CombatError::OutOfRange(self.characters[target].display_name(), range));
+        }
+        self.checkpoint();
+        let attacker = self.characters[source].char_ref();
+        let defender = self.characters[target].char_ref();
+        let success = self.apply_gambit(source, target, Gambit::Grapple, roll, None);
+        if success {
+            let control = std::cmp::max(control, 1);
+            let condition = Condition::grappled().with_source(ConditionSource::Grapple(grappler));
+            self.characters[target].apply_condition(condition);
+            self.grapples.push(Grapple::new(grappler, victim, control));
+            self.log(CombatEvent::Grappled {
+                grappler: attacker,
+                victim: defender,
+                control,
+            });
+        }
+        self.update();
+        self.follow_turn();
+        self.check_over();
+        Ok(success)
+    }
+
+    //Savages the victim, damage with a type is decisive and without it withering
+    pub fn savage(
+        &mut self, grappler: CharId, damage: i32, kind: Option<DamageType>,
+    ) -> Result<Outcome, CombatError> {
+        let index = self.check_grapple(grappler)?;
+        let (source, target) = match kind {
+            Some(_) => self.check_decisive(grappler, self.grapples[index].victim)?,
+            None => self.check_attack(grappler, self.grapples[index].victim)?,
+        };
+        self.checkpoint();
+        self.log(CombatEvent::GrappleAction {
+            grappler: self.characters[source].char_ref(),
+            victim: self.characters[target].char_ref(),
+            action: GrappleAction::Savage,
+        });
+        //Control is spent up front, the attack can end the round and with it the grapple
+        let last = self.grapples[index].spend();
+        let outcome = match kind {
+            Some(x) => self.resolve_decisive(source, target, Some(damage), x, None),
+            None => self.resolve_withering(source, target, Some(damage), None),
+        };
+        if last {
+            if let Some(x) = self.grapples.iter().position(|x| x.grappler == grappler) {
+                self.end_grapple(x);
+            }
+        }
+        Ok(outcome)
+    }
+
+    //Throws the victim to the ground, ending the grapple, or restrains them for a round
+    pub fn grapple_action(
+        &mut self, grappler: CharId, action: GrappleAction,
+    ) -> Result<(), CombatError> {
+        let index = self.check_grapple(grappler)?;
+        let (source, target) = (self.index(grappler)?, self.index(self.grapples[index].victim)?);
+        if action == GrappleAction::Savage {
+            return Err(CombatError::NotSavage);
+        }
+        self.checkpoint();
+        self.log(CombatEvent::GrappleAction {
+            grappler: self.characters[source].char_ref(),
+            victim: self.characters[target].char_ref(),
+            action,
+        });
+        let released = match action {
+            GrappleAction::Savage => false,
+            GrappleAction::Throw => {
+                self.characters[target].apply_condition(Condition::prone());
+                true
+            }
+            GrappleAction::Restrain => {
+                self.characters[target].done = true;
+                self.grapples[index].spend()
+            }
+        };
+        if released {
+            self.end_grapple(index);
+        }
+        self.char_by_id_mut(grappler).unwrap().finish();
+        self.update();
+        self.follow_turn();
+        self.check_over();
+        Ok(())
+    }
+
+    //Lets go of the character the grappler is holding
+    pub fn release(&mut self, grappler: CharId) -> Result<(), CombatError> {
+        let index = self.index(grappler)?;
+        let grapple = match self.grapples.iter().position(|x| x.grappler == grappler) {
+            Some(x) => x,
+            None => return Err(CombatError::NotGrappling(self.characters[index].display_name())),
+        };
+        self.checkpoint();
+        self.end_grapple(grapple);
+        Ok(())
+    }
+
+    //Resolves the victim's contested roll to break free, which takes their turn either way
+    pub fn escape(&mut self, victim: CharId, success: bool) -> Result<(), CombatError> {
+        let index = self.index(victim)?;
+        let char = &self.characters[index];
+        if char.incapacitated() {
+            return Err(CombatError::Incapacitated(char.display_name()));
+        }
+        let grapple = match self.grapples.iter().position(|x| x.victim == victim) {
+            Some(x) => x,
+            None => return Err(CombatError::NotHeld(char.display_name())),
+        };
+        self.checkpoint();
+        let grappler = self.grapples[grapple].grappler;
+        self.log(CombatEvent::GrappleEscape {
+            victim: self.characters[index].char_ref(),
+            grappler: self.char_by_id(grappler).unwrap().char_ref(),
+            success,
+        });
+        if success {
+            self.end_grapple(grapple);
+        }
+        self.char_by_id_mut(victim).unwrap().finish();
+        self.update();
+        self.follow_turn();
+        Ok(())
+    }
+
+    //Finds the grapple a grappler holds that still has control to use this round
+    fn check_grapple(&self, grappler: CharId) -> Result<usize, CombatError> {
+        let char = &self.characters[self.index(grappler)?];
+        if char.incapacitated() {
+            return Err(CombatError::Incapacitated(char.display_name()));
+        }
+        match self.grapples.iter().position(|x| x.grappler == grappler) {
+            Some(x) if self.grapples[x].spent => Err(CombatError::ControlSpent(char.display_name())),
+            Some(x) => Ok(x),
+            None => Err(CombatError::NotGrappling(char.display_name())),
+        }
+    }
+
+    fn end_grapple(&mut self, index: usize) {
+        let grapple = self.grapples.remove(index);
+        let source = ConditionSource::Grapple(grapple.grappler);
+        if let Some(x) = self.char_by_id_mut(grapple.victim) {
+            x.conditions.retain(|x| x.source != source);
+        }
+        let name = |x: CharId| self.char_by_id(x).map(|y| y.char_ref());
+        if let (Some(grappler), Some(victim)) = (name(grapple.grappler), name(grapple.victim)) {
+            self.log(CombatEvent::GrappleReleased { grappler, victim });
+        }
+    }
+
+    //Puts a condition on a character, replacing the same one
+    pub fn add_condition(&mut self, id: CharId, condition: Condition) -> Result<(), CombatError> {
+        let index = self.index(id)?;
+        self.checkpoint();
+        self.log(CombatEvent::ConditionAdded {
+            character: self.characters[index].char_ref(),
+            condition: condition.name.clone(),
+            duration: condition.duration,
+        });
+        self.characters[index].apply_condition(condition);
+        Ok(())
+    }
+
+    //Takes a condition off, a condition a grapple put on goes by ending that grapple
+    pub fn remove_condition(&mut self, id: CharId, name: &str) -> Result<(), CombatError> {
+        let index = self.index(id)?;
+        let condition = match self.characters[index].conditions.iter().find(|x| x.name == name) {
+            Some(x) => x.clone(),
+            None => return Err(CombatError::NoCondition(name.to_string())),
+        };
+        self.checkpoint();
+        if let ConditionSource::Grapple(grappler) = condition.source {
+            if let Some(x) = self.grapples.iter().position(|x| x.grappler == grappler) {
+                self.end_grapple(x);
+                return Ok(());
+            }
+        }
+        self.characters[index].conditions.retain(|x| !x.same_as(&condition));
+        self.log(CombatEvent::ConditionRemoved {
+            character: self.characters[index].char_ref(),
+            condition: name.to_string(),
+        });
+        Ok(())
+    }
+
+    //Pays for a charm, motes coming from the peripheral pool first, and starts its duration
+    pub fn activate_charm(&mut self, id: CharId, charm: &Charm) -> Result<(), CombatError> {
+        let index = self.index(id)?;
+        let char = &self.characters[index];
+        let cost = charm.cost;
+        if charm.kind == CharmType::Permanent {
+            return Err(CombatError::Permanent(charm.name.clone()));
+        }
+        if cost.motes > char.peripheral.current + char.personal.current {
+            return Err(CombatError::NotEnoughMotes(char.display_name()));
+        }
+        if cost.willpower > char.willpower.current {
+            return Err(CombatError::NotEnough(char.display_name(), Resource::Willpower));
+        }
+        if cost.initiative > char.initiative {
+            return Err(CombatError::NoInitiative(char.display_name()));
+        }
+        let anima = char.anima;
+        self.checkpoint();
+        let char = &mut self.characters[index];
+        let peripheral = std::cmp::min(cost.motes, char.peripheral.current);
+        char.peripheral.spend(peripheral);
+        char.personal.spend(cost.motes - peripheral);
+        char.flare(peripheral);
+        char.willpower.spend(cost.willpower);
+        char.initiative -= cost.initiative;
+        if !matches!(charm.duration, Duration::Instant) {
+            char.effects.retain(|x| x.name != charm.name);
+            char.effects.push(ActiveEffect {
+                name: charm.name.clone(),
+                duration: charm.duration,
+            });
+        }
+        self.log(CombatEvent::CharmActivated {
+            character: self.characters[index].char_ref(),
+            charm: charm.name.clone(),
+            cost,
+        });
+        self.log_anima(index, anima);
+        self.update();
+        Ok(())
+    }
+
+    //Dismisses a charm's effect before its duration runs out
+    pub fn end_effect(&mut self, id: CharId, name: &str) -> Result<(), CombatError> {
+        let index = self.index(id)?;
+        if !self.characters[index].effects.iter().any(|x| x.name == name) {
+            return Err(CombatError::NoEffect(name.to_string()));
+        }
+        self.checkpoint();
+        self.characters[index].effects.retain(|x| x.name != name);
+        self.log(CombatEvent::EffectEnded {
+            character: self.characters[index].char_ref(),
+            effect: name.to_string(),
+        });
+        Ok(())
+    }
+
+    fn log_anima(&mut self, index: usize, before: Anima) {
+        if self.characters[index].anima != before {
+            self.log(CombatEvent::AnimaChanged {
+                character: self.characters[index].char_ref(),
+                anima: self.characters[index].anima,
+            });
+        }
+    }
+
+    pub fn set_side(&mut self, id: CharId, side: Side) -> Result<(), CombatError> {
+        let index = self.index(id)?;
+        self.checkpoint();
+        self.log(CombatEvent::ManualEdit {
+            character: self.characters[index].char_ref(),
+            field: "side".to_string(),
+            value: side.to_string(),
+        });
+        self.characters[index].side = side;
+        self.check_over();
+        Ok(())
+    }
+
+    //Every side with someone on it, in turn order of their first member
+    pub fn sides(&self) -> Vec<Side> {
+        let mut sides: Vec<Side> = Vec::new();
+        for char in &self.characters {
+            if !sides.contains(&char.side) {
+                sides.push(char.side.clone());
+            }
+        }
+        sides
+    }
+
+    //Characters on a side who are still able to fight
+    pub fn living<'a>(&'a self, side: &'a Side) -> impl Iterator<Item = &'a Character> {
+        self.characters.iter().filter(move |x| x.side == *side && !x.incapacitated())
+    }
+
+    //Sides other than neutral with someone still able to fight
+    fn standing_sides(&self) -> Vec<Side> {
+        self.sides()
+            .into_iter()
+            .filter(|x| *x != Side::Neutral && self.living(x).next().is_some())
+            .collect()
+    }
+
+    //True once at most one of the fighting sides has anyone left standing
+    pub fn is_over(&self) -> bool {
+        let fighting = self.sides().iter().filter(|x| **x != Side::Neutral).count();
+        fighting >= 2 && self.standing_sides().len() <= 1
+    }
+
+    pub fn summary(&self) -> CombatSummary {
+        CombatSummary::new(self)
+    }
+
+    pub fn winner(&self) -> Option<Side> {
+        if self.is_over() {
+            self.standing_sides().pop()
+        } else {
+            None
+        }
+    }
+
+    //Logs the end of combat the moment it happens
+    fn check_over(&mut self) {
+        let over = self.is_over();
+        if over && !self.over {
+            self.log(CombatEvent::CombatOver {
+                winner: self.winner(),
+            });
+        }
+        self.over = over;
+    }
+
+    //Characters hostile to a side who are still able to fight
+    pub fn living_enemies<'a>(&'a self, side: &'a Side) -> impl Iterator<Item = &'a Character> {
+        self.characters.iter().filter(move |x| x.side.hostile_to(side) && !x.incapacitated())
     }
 
     pub fn new_round(&mut self) {
-        for char in &mut self.characters {
+        self.checkpoint();
+        self.start_round();
+        if self.current.is_some() {
+            self.advance();
+        }
+    }
+
+    fn start_round(&mut self) {
+        self.round += 1;
+        self.log(CombatEvent::RoundStart);
+        for index in 0..self.characters.len() {
+            let anima = self.characters[index].anima;
+            let char = &mut self.characters[index];
             char.ready();
+            char.respire(rules::MOTE_RESPIRATION);
+            char.fade_anima();
+            char.effects.retain_mut(|x| !x.tick_round());
+            char.tick_conditions(Duration::tick_round);
+            self.log_anima(index, anima);
+        }
+        for index in (0..self.grapples.len()).rev() {
+            let grapple = &self.grapples[index];
+            let held = [grapple.grappler, grapple.victim]
+                .iter()
+                .all(|x| self.char_by_id(*x).is_some_and(|y| !y.incapacitated()));
+            if self.grapples[index].tick_round() || !held {
+                self.end_grapple(index);
+            }
         }
         self.update();
     }
 
-    pub fn add_char(&mut self, mut char: Character) {
-        char.reset();
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    //The character whose turn it is, None until the first call to next_turn
+    pub fn current(&self) -> Option<CharId> {
+        self.current
+    }
+
+    //Ends the current actor's turn and starts the next one
+    pub fn next_turn(&mut self) -> Option<CharId> {
+        self.checkpoint();
+        if let Some(index) = self.current.and_then(|x| self.position_of(x)) {
+            if !self.characters[index].done {
+                self.characters[index].finish();
+                self.log(CombatEvent::TurnFinished {
+                    character: self.characters[index].char_ref(),
+                });
+                self.update();
+            }
+        }
+        self.advance()
+    }
+
+    //Picks the next actor, starting a new round when everyone has acted
+    fn advance(&mut self) -> Option<CharId> {
+        self.current = self.next_actor();
+        if self.current.is_none() {
+            //Delayed actions that were never taken come at the end of the round
+            self.current =
+                self.characters.iter().find(|x| x.delayed && !x.incapacitated()).map(|x| x.id);
+            if let Some(id) = self.current {
+                self.char_by_id_mut(id).unwrap().delayed = false;
+            }
+        }
+        if self.current.is_none() && self.characters.iter().any(|x| !x.incapacitated()) {
+            self.start_round();
+            self.current = self.next_actor();
+        }
+        if let Some(id) = self.current {
+            self.log(CombatEvent::TurnStarted {
+                character: self.char_by_id(id).unwrap().char_ref(),
+            });
+        }
+        self.current
+    }
+
+    //Highest initiative among characters who can still act, a coin flip settles full ties
+    fn next_actor(&mut self) -> Option<CharId> {
+        let ready: Vec<&Character> = self.characters.iter().filter(|x| x.can_act()).collect();
+        let first = ready.first()?.sortkey();
+        let tied: Vec<CharId> =
+            ready.iter().filter(|x| x.sortkey() == first).map(|x| x.id).collect();
+        match tied.len() {
+            1 => Some(tied[0]),
+            n => Some(tied[self.rng.gen_range(0..n)]),
+        }
+    }
+
+    //Steps a character out of the turn order until they resume or the round ends
+    pub fn delay(&mut self, id: CharId) -> Result<(), CombatError> {
+        let index = self.index(id)?;
+        if !self.characters[index].can_act() {
+            return Err(CombatError::CannotDelay(self.characters[index].display_name()));
+        }
+        self.checkpoint();
+        self.characters[index].delayed = true;
+        self.log(CombatEvent::Delayed {
+            character: self.characters[index].char_ref(),
+        });
+        self.follow_turn();
+        self.check_over();
+        Ok(())
+    }
+
+    //Brings a delayed character back in, taking their turn right away
+    pub fn resume(&mut self, id: CharId) -> Result<(), CombatError> {
+        let index = self.index(id)?;
+        if !self.characters[index].delayed {
+            return Err(CombatError::NotDelayed(self.characters[index].display_name()));
+        }
+        self.checkpoint();
+        self.characters[index].delayed = false;
+        self.current = Some(id);
+        self.log(CombatEvent::TurnStarted {
+            character: self.characters[index].char_ref(),
+        });
+        Ok(())
+    }
+
+    //Moves on once the current actor has finished, been taken out or removed
+    fn follow_turn(&mut self) {
+        if let Some(id) = self.current {
+            match self.char_by_id(id) {
+                Some(x) if x.can_act() => {}
+                _ => {
+                    self.advance();
+                }
+            }
+        }
+    }
+
+    //Adds a character mid-combat, rolling their Join Battle
+    pub fn join_combat(&mut self, char: Character) -> CharId {
+        self.checkpoint();
+        self.enter(char)
+    }
+
+    pub fn add_char(&mut self, mut char: Character) -> RollResult {
+        let roll = char.reset(&mut self.rng);
+        char.id = self.next_id;
+        self.next_id += 1;
         self.characters.push(char);
         self.update();
+        roll
     }
 
     pub fn count_name(&self, name: &str) -> usize {
         self.characters.iter().filter(|x| x.name == name).count()
     }
 
-    pub fn removechar(&mut self, index: usize) {
-        self.characters.remove(index);
+    pub fn remove_char(&mut self, id: CharId) -> Result<(), CombatError> {
+        self.index(id)?;
+        if self.characters.len() <= 1 {
+            return Err(CombatError::LastCharacter);
+        }
+        self.checkpoint();
+        if let Some(x) = self.grapples.iter().position(|x| x.involves(id)) {
+            self.end_grapple(x);
+        }
+        self.ranges.retain(|x| !x.involves(id));
+        let char = self.characters.remove(self.index(id)?);
+        self.log(CombatEvent::CharacterRemoved {
+            character: char.char_ref(),
+        });
+        self.follow_turn();
+        self.check_over();
+        Ok(())
     }
 
     pub fn reset(&mut self) {
+        self.history.clear();
+        self.future.clear();
         self.log.clear();
-        self.characters = Character::load_characters();
-        self.update();
+        self.reverted.clear();
+        self.characters.clear();
+        self.round = Encounter::default_round();
+        self.current = None;
+        self.over = false;
+        self.grapples.clear();
+        self.ranges.clear();
+        self.join_battle(Character::load_characters());
     }
 
     pub fn update(&mut self) {
+        for index in 0..self.characters.len() {
+            for condition in std::mem::take(&mut self.characters[index].expired) {
+                self.log(CombatEvent::ConditionExpired {
+                    character: self.characters[index].char_ref(),
+                    condition: condition.name,
+                });
+            }
+        }
         self.characters.sort_by_key(|c| c.sortkey());
     }
+
+    //Saves from before characters had ids get them here, and their crasher by name
+    fn migrate(&mut self) {
+        for char in self.characters.iter_mut() {
+            if char.id == 0 {
+                char.id = self.next_id;
+                self.next_id += 1;
+            }
+            self.next_id = std::cmp::max(self.next_id, char.id + 1);
+        }
+        for index in 0..self.characters.len() {
+            if let Some(name) = self.characters[index].crasher_name.take() {
+                let crasher = self.characters.iter().find(|x| x.name == name).map(|x| x.id);
+                self.characters[index].crasher = self.characters[index].crasher.or(crasher);
+            }
+        }
+    }
+
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, serde_json::to_string(self)?)
+    }
+
+    //Reads a saved encounter, bringing saves from older versions up to date
+    pub fn parse(text: &str) -> serde_json::Result<Encounter> {
+        let mut encounter: Encounter = serde_json::from_str(text)?;
+        encounter.migrate();
+        encounter.update();
+        Ok(encounter)
+    }
+
+    pub fn load(path: &str) -> std::io::Result<Encounter> {
+        Ok(Encounter::parse(&std::fs::read_to_string(path)?)?)
+    }
 }
 
 pub struct MonsterDB {
@@ -276,9 +2170,7 @@ impl MonsterDB {
     pub(crate) fn get_monster_by_name(&self, name: &str) -> Option<Character> {
         for monster in self.monsters.iter() {
             if monster.name == name {
-                let mut monster_copy = monster.clone();
-                monster_copy.reset();
-                return Some(monster_copy);
+                return Some(monster.clone());
             }
         }
         None
@@ -0,0 +1,105 @@
+use crate::charms::Duration;
+use crate::combat::CharId;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+//Something affecting a character for a while, like being prone or poisoned
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub name: String,
+    #[serde(default = "Condition::default_duration")]
+    pub duration: Duration,
+    //Added to evasion and parry
+    #[serde(default)]
+    pub defense: i32,
+    //Added to attack dice pools
+    #[serde(default)]
+    pub pools: i32,
+    #[serde(default)]
+    pub source: ConditionSource,
+    //Shown in the participant list instead of the name's first letter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<char>,
+}
+
+//What put a condition on a character, so it can be taken off again with its cause
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConditionSource {
+    #[default]
+    Other,
+    //Held in a grapple by this character
+    Grapple(CharId),
+}
+
+impl Condition {
+    pub fn new(name: &str, defense: i32, pools: i32) -> Condition {
+        Condition {
+            name: name.to_string(),
+            duration: Condition::default_duration(),
+            defense,
+            pools,
+            source: ConditionSource::Other,
+            icon: None,
+        }
+    }
+    fn default_duration() -> Duration { Duration::Indefinite }
+
+    pub fn prone() -> Condition {
+        Condition::new("Prone", -1, -3)
+    }
+
+    pub fn blinded() -> Condition {
+        Condition::new("Blinded", -2, -3)
+    }
+
+    pub fn grappled() -> Condition {
+        Condition::new("Grappled", -2, 0)
+    }
+
+    pub fn poisoned() -> Condition {
+        Condition::new("Poisoned", 0, -2).with_icon('V')
+    }
+
+    //Common conditions offered when marking a character
+    pub fn presets() -> Vec<Condition> {
+        vec![Condition::prone(), Condition::blinded(), Condition::grappled(), Condition::poisoned()]
+    }
+
+    pub fn with_duration(mut self, duration: Duration) -> Condition {
+        self.duration = duration;
+        self
+    }
+
+    pub fn with_icon(mut self, icon: char) -> Condition {
+        self.icon = Some(icon);
+        self
+    }
+
+    pub fn with_source(mut self, source: ConditionSource) -> Condition {
+        self.source = source;
+        self
+    }
+
+    //True when adding other would replace this condition
+    pub fn same_as(&self, other: &Condition) -> bool {
+        self.name == other.name && self.source == other.source
+    }
+
+    //Letter shown next to the character in the participant list
+    pub fn icon(&self) -> char {
+        self.icon.unwrap_or_else(|| self.name.chars().next().unwrap_or('?').to_ascii_uppercase())
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({}", self.name, self.duration)?;
+        if self.defense != 0 {
+            write!(f, ", {:+} def", self.defense)?;
+        }
+        if self.pools != 0 {
+            write!(f, ", {:+} dice", self.pools)?;
+        }
+        write!(f, ")")
+    }
+}
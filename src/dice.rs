@@ -0,0 +1,126 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+//Describes an exalted dice roll before it is rolled
+#[derive(Debug, Clone, PartialEq)]
+pub struct DicePool {
+    pub dice: i32,
+    pub target: i32,
+    pub double: i32,
+    pub automatic: i32,
+    pub reroll: Vec<i32>,
+}
+
+//The outcome of rolling a DicePool, with every face that stayed on the table
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct RollResult {
+    pub faces: Vec<i32>,
+    pub rerolled: Vec<i32>,
+    pub successes: i32,
+    pub botch: bool,
+}
+
+impl DicePool {
+    pub fn new(dice: i32) -> DicePool {
+        DicePool {
+            dice,
+            target: 7,
+            double: 10,
+            automatic: 0,
+            reroll: Vec::new(),
+        }
+    }
+
+    //Damage rolls do not count 10s twice
+    pub fn damage(dice: i32) -> DicePool {
+        DicePool::new(dice).double(11)
+    }
+
+    pub fn target(mut self, target: i32) -> DicePool {
+        self.target = target;
+        self
+    }
+
+    pub fn double(mut self, double: i32) -> DicePool {
+        self.double = double;
+        self
+    }
+
+    pub fn automatic(mut self, automatic: i32) -> DicePool {
+        self.automatic = automatic;
+        self
+    }
+
+    pub fn reroll(mut self, face: i32) -> DicePool {
+        if !self.reroll.contains(&face) {
+            self.reroll.push(face);
+        }
+        self
+    }
+
+    pub fn face_value(&self, face: i32) -> i32 {
+        if face >= self.double {
+            2
+        } else if face >= self.target {
+            1
+        } else {
+            0
+        }
+    }
+
+    pub fn roll<R: Rng + ?Sized>(&self, rng: &mut R) -> RollResult {
+        let mut faces = Vec::new();
+        let mut rerolled = Vec::new();
+        //Rerolling every face would never finish
+        let can_reroll = self.reroll.len() < 10;
+        for _ in 0..self.dice {
+            let mut face = rng.gen_range(1..=10);
+            while can_reroll && self.reroll.contains(&face) {
+                rerolled.push(face);
+                face = rng.gen_range(1..=10);
+            }
+            faces.push(face);
+        }
+        self.score(faces, rerolled)
+    }
+
+    //Counts a set of faces that were rolled at the table
+    pub fn score(&self, faces: Vec<i32>, rerolled: Vec<i32>) -> RollResult {
+        let successes = faces.iter().map(|x| self.face_value(*x)).sum::<i32>() + self.automatic;
+        let botch = successes == 0 && faces.contains(&1);
+        RollResult {
+            faces,
+            rerolled,
+            successes,
+            botch,
+        }
+    }
+}
+
+impl RollResult {
+    pub fn ones(&self) -> i32 {
+        self.faces.iter().filter(|x| **x == 1).count() as i32
+    }
+
+    //Successes, or minus the number of 1s if nothing succeeded
+    pub fn net(&self) -> i32 {
+        if self.successes > 0 {
+            self.successes
+        } else {
+            -self.ones()
+        }
+    }
+}
+
+impl fmt::Display for RollResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let faces: Vec<String> = self.faces.iter().map(|x| x.to_string()).collect();
+        write!(f, "[{}] ", faces.join(" "))?;
+        if self.botch {
+            write!(f, "botch")
+        } else {
+            write!(f, "{} suc", self.successes)
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+//Something a character spends and regains, like motes or willpower
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(from = "PoolRecord", into = "PoolRecord")]
+pub struct Pool {
+    pub current: i32,
+    pub max: i32,
+}
+
+//Character files give just the size of a pool, saves also what is left of it
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum PoolRecord {
+    Max(i32),
+    Full { current: i32, max: i32 },
+}
+
+impl From<PoolRecord> for Pool {
+    fn from(record: PoolRecord) -> Pool {
+        match record {
+            PoolRecord::Max(x) => Pool::new(x),
+            PoolRecord::Full { current, max } => Pool { current, max },
+        }
+    }
+}
+
+impl From<Pool> for PoolRecord {
+    fn from(pool: Pool) -> PoolRecord {
+        PoolRecord::Full {
+            current: pool.current,
+            max: pool.max,
+        }
+    }
+}
+
+impl Pool {
+    pub fn new(max: i32) -> Pool {
+        Pool { current: max, max }
+    }
+
+    pub fn spend(&mut self, amount: i32) -> bool {
+        if amount > self.current {
+            return false;
+        }
+        self.current -= amount;
+        true
+    }
+
+    //Fills the pool up to its maximum, returning what did not fit
+    pub fn regain(&mut self, amount: i32) -> i32 {
+        let gained = std::cmp::min(amount, self.max - self.current).max(0);
+        self.current += gained;
+        amount - gained
+    }
+}
+
+impl fmt::Display for Pool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.current, self.max)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    Personal,
+    Peripheral,
+    Willpower,
+}
+
+impl Resource {
+    pub fn all() -> [Resource; 3] {
+        [Resource::Personal, Resource::Peripheral, Resource::Willpower]
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Resource::Personal => write!(f, "personal motes"),
+            Resource::Peripheral => write!(f, "peripheral motes"),
+            Resource::Willpower => write!(f, "willpower"),
+        }
+    }
+}
+
+//How brightly an Exalt's anima banner burns
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Anima {
+    #[default]
+    Dim,
+    Glowing,
+    Burning,
+    Bonfire,
+}
+
+impl Anima {
+    pub fn rise(self, levels: i32) -> Anima {
+        (0..levels).fold(self, |x, _| match x {
+            Anima::Dim => Anima::Glowing,
+            Anima::Glowing => Anima::Burning,
+            _ => Anima::Bonfire,
+        })
+    }
+
+    pub fn fall(self) -> Anima {
+        match self {
+            Anima::Bonfire => Anima::Burning,
+            Anima::Burning => Anima::Glowing,
+            _ => Anima::Dim,
+        }
+    }
+}
+
+impl fmt::Display for Anima {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Anima::Dim => write!(f, "Dim"),
+            Anima::Glowing => write!(f, "Glowing"),
+            Anima::Burning => write!(f, "Burning"),
+            Anima::Bonfire => write!(f, "Bonfire"),
+        }
+    }
+}
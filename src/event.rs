@@ -0,0 +1,343 @@
+use crate::charms::{Cost, Duration};
+use crate::combat::{AttackRoll, CharId, DamageType, Side};
+use crate::gambit::Gambit;
+use crate::grapple::GrappleAction;
+use crate::range::{Movement, Range};
+use crate::dice::RollResult;
+use crate::essence::{Anima, Resource};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+
+//A character as they were named when an event happened
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CharRef {
+    pub id: CharId,
+    pub name: String,
+}
+
+impl fmt::Display for CharRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+//Everything that happens in an encounter, the combat log is rendered from these
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum CombatEvent {
+    Note(String),
+    CharacterJoined {
+        character: CharRef,
+        roll: Option<RollResult>,
+    },
+    CharacterRemoved {
+        character: CharRef,
+    },
+    RoundStart,
+    TurnStarted {
+        character: CharRef,
+    },
+    TurnFinished {
+        character: CharRef,
+    },
+    Delayed {
+        character: CharRef,
+    },
+    WitheringHit {
+        attacker: CharRef,
+        defender: CharRef,
+        damage: i32,
+        roll: Option<AttackRoll>,
+    },
+    WitheringMiss {
+        attacker: CharRef,
+        defender: CharRef,
+        roll: Option<AttackRoll>,
+    },
+    DecisiveHit {
+        attacker: CharRef,
+        defender: CharRef,
+        damage: i32,
+        kind: DamageType,
+        roll: Option<AttackRoll>,
+    },
+    DecisiveMiss {
+        attacker: CharRef,
+        defender: CharRef,
+        roll: Option<AttackRoll>,
+    },
+    Crash {
+        attacker: CharRef,
+        defender: CharRef,
+    },
+    InitiativeBreak {
+        character: CharRef,
+        bonus: i32,
+    },
+    InitiativeShift {
+        character: CharRef,
+        initiative: i32,
+        roll: Option<RollResult>,
+    },
+    ManualEdit {
+        character: CharRef,
+        field: String,
+        value: String,
+    },
+    Spent {
+        character: CharRef,
+        resource: Resource,
+        amount: i32,
+    },
+    CharmActivated {
+        character: CharRef,
+        charm: String,
+        cost: Cost,
+    },
+    EffectEnded {
+        character: CharRef,
+        effect: String,
+    },
+    Moved {
+        character: CharRef,
+        other: CharRef,
+        movement: Movement,
+        range: Range,
+    },
+    GambitPaid {
+        character: CharRef,
+        cost: i32,
+    },
+    Gambit {
+        attacker: CharRef,
+        defender: CharRef,
+        gambit: Gambit,
+        success: bool,
+        roll: Option<AttackRoll>,
+    },
+    Grappled {
+        grappler: CharRef,
+        victim: CharRef,
+        control: i32,
+    },
+    GrappleAction {
+        grappler: CharRef,
+        victim: CharRef,
+        action: GrappleAction,
+    },
+    GrappleReleased {
+        grappler: CharRef,
+        victim: CharRef,
+    },
+    GrappleEscape {
+        victim: CharRef,
+        grappler: CharRef,
+        success: bool,
+    },
+    ConditionAdded {
+        character: CharRef,
+        condition: String,
+        duration: Duration,
+    },
+    ConditionRemoved {
+        character: CharRef,
+        condition: String,
+    },
+    ConditionExpired {
+        character: CharRef,
+        condition: String,
+    },
+    AnimaChanged {
+        character: CharRef,
+        anima: Anima,
+    },
+    SizeLost {
+        character: CharRef,
+        size: i32,
+    },
+    RoutCheck {
+        character: CharRef,
+        roll: RollResult,
+        routed: bool,
+    },
+    CombatOver {
+        winner: Option<Side>,
+    },
+    Undo(Option<Box<CombatEvent>>),
+    Redo(Option<Box<CombatEvent>>),
+}
+
+fn fmt_roll(f: &mut fmt::Formatter, roll: &Option<AttackRoll>) -> fmt::Result {
+    match roll {
+        Some(x) => write!(f, " {}", x),
+        None => Ok(()),
+    }
+}
+
+impl fmt::Display for CombatEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CombatEvent::Note(x) => write!(f, "{}", x),
+            CombatEvent::CharacterJoined { character, roll } => {
+                write!(f, "{} joined combat!", character)?;
+                match roll {
+                    Some(x) => write!(f, " JB: {}", x),
+                    None => Ok(()),
+                }
+            }
+            CombatEvent::CharacterRemoved { character } => {
+                write!(f, "{} was removed from combat.", character)
+            }
+            CombatEvent::RoundStart => write!(f, "New round!"),
+            CombatEvent::TurnStarted { character } => write!(f, "It is {}'s turn.", character),
+            CombatEvent::Delayed { character } => write!(f, "{} delays their action.", character),
+            CombatEvent::TurnFinished { character } => {
+                write!(f, "{} finished their turn.", character)
+            }
+            CombatEvent::WitheringHit { attacker, defender, damage, roll } => {
+                write!(
+                    f,
+                    "{} hits a withering attack on {} for {} damage.",
+                    attacker, defender, damage
+                )?;
+                fmt_roll(f, roll)
+            }
+            CombatEvent::WitheringMiss { attacker, defender, roll } => {
+                write!(f, "{} misses withering attack on {}.", attacker, defender)?;
+                fmt_roll(f, roll)
+            }
+            CombatEvent::DecisiveHit { attacker, defender, damage, kind, roll } => {
+                write!(
+                    f,
+                    "{} hits decisive attack on {}, with {}{} damage!",
+                    attacker,
+                    defender,
+                    damage,
+                    kind.suffix()
+                )?;
+                fmt_roll(f, roll)
+            }
+            CombatEvent::DecisiveMiss { attacker, defender, roll } => {
+                write!(f, "{} misses decisive attack on {}.", attacker, defender)?;
+                fmt_roll(f, roll)
+            }
+            CombatEvent::Crash { attacker, defender } => {
+                write!(f, "{} crashed {}!", attacker, defender)
+            }
+            CombatEvent::InitiativeBreak { character, bonus } => {
+                write!(f, "{} gains {} initiative from Initiative Break.", character, bonus)
+            }
+            CombatEvent::InitiativeShift { character, initiative, roll } => {
+                write!(f, "{} shifts to {} initiative and acts again!", character, initiative)?;
+                match roll {
+                    Some(x) => write!(f, " JB: {}", x),
+                    None => Ok(()),
+                }
+            }
+            CombatEvent::ManualEdit { character, field, value } => {
+                write!(f, "{} {} set to {}.", character, field, value)
+            }
+            CombatEvent::Spent { character, resource, amount } => match *amount < 0 {
+                true => write!(f, "{} regains {} {}.", character, -amount, resource),
+                false => write!(f, "{} spends {} {}.", character, amount, resource),
+            },
+            CombatEvent::CharmActivated { character, charm, cost } => {
+                write!(f, "{} activates {} ({}).", character, charm, cost)
+            }
+            CombatEvent::EffectEnded { character, effect } => {
+                write!(f, "{}'s {} ends.", character, effect)
+            }
+            CombatEvent::Moved { character, other, movement, range } => match movement {
+                Movement::Rush(_) => {
+                    write!(f, "{} rushes {}, now at {} range.", character, other, range)
+                }
+                Movement::Disengage(_) => {
+                    write!(f, "{} disengages from {}, now at {} range.", character, other, range)
+                }
+                _ => write!(f, "{} is now at {} range of {}.", character, range, other),
+            },
+            CombatEvent::GambitPaid { character, cost } => {
+                write!(f, "{} pays {} initiative for the gambit.", character, cost)
+            }
+            CombatEvent::Gambit { attacker, defender, gambit, success, roll } => {
+                let (verb, verbs) = gambit.verbs();
+                match success {
+                    true => write!(f, "{} {} {}!", attacker, verbs, defender)?,
+                    false => write!(f, "{} fails to {} {}.", attacker, verb, defender)?,
+                }
+                fmt_roll(f, roll)
+            }
+            CombatEvent::Grappled { grappler, victim, control } => {
+                let rounds = if *control == 1 { "round" } else { "rounds" };
+                write!(f, "{} holds {} for {} {}.", grappler, victim, control, rounds)
+            }
+            CombatEvent::GrappleAction { grappler, victim, action } => match action {
+                GrappleAction::Savage => write!(f, "{} savages {}.", grappler, victim),
+                GrappleAction::Throw => write!(f, "{} throws {} to the ground.", grappler, victim),
+                GrappleAction::Restrain => write!(f, "{} restrains {}.", grappler, victim),
+            },
+            CombatEvent::GrappleReleased { grappler, victim } => {
+                write!(f, "{} no longer holds {}.", grappler, victim)
+            }
+            CombatEvent::GrappleEscape { victim, grappler, success } => match success {
+                true => write!(f, "{} breaks free of {}!", victim, grappler),
+                false => write!(f, "{} struggles against {} in vain.", victim, grappler),
+            },
+            CombatEvent::ConditionAdded { character, condition, duration } => {
+                write!(f, "{} is {} ({}).", character, condition, duration)
+            }
+            CombatEvent::ConditionRemoved { character, condition } => {
+                write!(f, "{} is no longer {}.", character, condition)
+            }
+            CombatEvent::ConditionExpired { character, condition } => {
+                write!(f, "{}'s {} wears off.", character, condition)
+            }
+            CombatEvent::AnimaChanged { character, anima } => {
+                write!(f, "{}'s anima is now {}.", character, anima)
+            }
+            CombatEvent::SizeLost { character, size } => match size {
+                0 => write!(f, "{} is wiped out!", character),
+                x => write!(f, "{} is reduced to size {}.", character, x),
+            },
+            CombatEvent::RoutCheck { character, roll, routed } => match routed {
+                true => write!(f, "{} routs! {}", character, roll),
+                false => write!(f, "{} holds together. {}", character, roll),
+            },
+            CombatEvent::CombatOver { winner } => match winner {
+                Some(x) => write!(f, "Combat is over, {} stand victorious!", x),
+                None => write!(f, "Combat is over, nobody is left standing."),
+            },
+            CombatEvent::Undo(x) => match x {
+                Some(x) => write!(f, "Undo: {}", x),
+                None => write!(f, "Undo."),
+            },
+            CombatEvent::Redo(x) => match x {
+                Some(x) => write!(f, "Redo: {}", x),
+                None => write!(f, "Redo."),
+            },
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LogRecord {
+    Event(CombatEvent),
+    Line(String),
+    Unknown(serde_json::Value),
+}
+
+//Reads a combat log, turning plain text lines from older saves and anything unreadable into notes
+pub fn deserialize_log<'de, D>(deserializer: D) -> Result<Vec<CombatEvent>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let records: Vec<LogRecord> = Vec::deserialize(deserializer)?;
+    Ok(records
+        .into_iter()
+        .map(|x| match x {
+            LogRecord::Event(x) => x,
+            LogRecord::Line(x) => CombatEvent::Note(x),
+            LogRecord::Unknown(x) => CombatEvent::Note(x.to_string()),
+        })
+        .collect())
+}
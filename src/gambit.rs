@@ -0,0 +1,63 @@
+use crate::charms::Duration;
+use crate::condition::Condition;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+//Decisive attacks that pay initiative to do something other than damage
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gambit {
+    Disarm,
+    Knockdown,
+    Distract,
+    Unhorse,
+    Grapple,
+}
+
+impl Gambit {
+    //Gambits that only leave a condition, grapples also need the rounds of control won
+    pub fn all() -> [Gambit; 4] {
+        [Gambit::Disarm, Gambit::Knockdown, Gambit::Distract, Gambit::Unhorse]
+    }
+
+    //Successes the initiative roll needs for the gambit to work
+    pub fn difficulty(&self) -> i32 {
+        match self {
+            Gambit::Disarm => 3,
+            Gambit::Knockdown => 2,
+            Gambit::Distract => 3,
+            Gambit::Unhorse => 4,
+            Gambit::Grapple => 2,
+        }
+    }
+
+    //What a successful gambit leaves on its target, a grapple puts its own on once it holds
+    pub fn condition(&self) -> Option<Condition> {
+        match self {
+            Gambit::Disarm => Some(Condition::new("Disarmed", 0, -2)),
+            Gambit::Knockdown => Some(Condition::prone()),
+            Gambit::Distract => {
+                let distracted = Condition::new("Distracted", -1, 0).with_icon('Z');
+                Some(distracted.with_duration(Duration::Rounds(1)))
+            }
+            Gambit::Unhorse => Some(Condition::new("Unhorsed", 0, 0)),
+            Gambit::Grapple => None,
+        }
+    }
+
+    //How the log words the gambit, as in "fails to disarm" and "disarms"
+    pub fn verbs(&self) -> (&'static str, &'static str) {
+        match self {
+            Gambit::Disarm => ("disarm", "disarms"),
+            Gambit::Knockdown => ("knock down", "knocks down"),
+            Gambit::Distract => ("distract", "distracts"),
+            Gambit::Unhorse => ("unhorse", "unhorses"),
+            Gambit::Grapple => ("grapple", "grapples"),
+        }
+    }
+}
+
+impl fmt::Display for Gambit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} ({})", self, self.difficulty())
+    }
+}
@@ -0,0 +1,79 @@
+use crate::combat::CharId;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+//One character holding another in a clinch
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Grapple {
+    pub grappler: CharId,
+    pub victim: CharId,
+    //Rounds of control the grappler has left
+    pub control: i32,
+    //True once the grappler has used this round's control
+    #[serde(default)]
+    pub spent: bool,
+}
+
+impl Grapple {
+    //Winning the grapple takes the grappler's turn, so that round costs no control
+    pub fn new(grappler: CharId, victim: CharId, control: i32) -> Grapple {
+        Grapple {
+            grappler,
+            victim,
+            control,
+            spent: true,
+        }
+    }
+
+    pub fn involves(&self, id: CharId) -> bool {
+        self.grappler == id || self.victim == id
+    }
+
+    //The other character in the grapple
+    pub fn partner(&self, id: CharId) -> CharId {
+        if self.grappler == id {
+            self.victim
+        } else {
+            self.grappler
+        }
+    }
+
+    //Uses up a round of control, returning true when none are left
+    pub fn spend(&mut self) -> bool {
+        self.control -= 1;
+        self.spent = true;
+        self.control <= 0
+    }
+
+    //A round in which the grappler did nothing with the clinch still costs a round of control
+    pub fn tick_round(&mut self) -> bool {
+        if !self.spent {
+            self.control -= 1;
+        }
+        self.spent = false;
+        self.control <= 0
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrappleAction {
+    Savage,
+    Throw,
+    Restrain,
+}
+
+impl GrappleAction {
+    pub fn all() -> [GrappleAction; 3] {
+        [GrappleAction::Savage, GrappleAction::Throw, GrappleAction::Restrain]
+    }
+}
+
+impl fmt::Display for GrappleAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GrappleAction::Savage => write!(f, "Savage"),
+            GrappleAction::Throw => write!(f, "Throw"),
+            GrappleAction::Restrain => write!(f, "Restrain"),
+        }
+    }
+}
@@ -1,4 +1,15 @@
+pub mod battlegroup;
+pub mod charms;
 pub mod combat;
+pub mod condition;
+pub mod dice;
+pub mod essence;
+pub mod event;
+pub mod gambit;
+pub mod grapple;
 pub mod mainwindow;
+pub mod range;
+pub mod rules;
+pub mod summary;
 pub mod textbox;
 pub mod util;
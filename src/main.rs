@@ -1,8 +1,81 @@
-use exalted_combat::mainwindow::MainWindow;
+use exalted_combat::combat::Encounter;
+use exalted_combat::mainwindow::{MainWindow, SAVE_FILE};
 use exalted_combat::util::Color;
 use exalted_combat::util::Drawable;
 
+//Prints the combat log of the saved encounter
+fn export_log() {
+    match Encounter::load(SAVE_FILE) {
+        Ok(encounter) => {
+            for event in encounter.log_iter() {
+                println!("{}", event);
+            }
+        }
+        Err(e) => {
+            eprintln!("Unable to read encounter from {}: {}", SAVE_FILE, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+//Picks up the saved encounter, or starts a new one when there is none
+fn load_encounter(seed: Option<u64>) -> Encounter {
+    match Encounter::load(SAVE_FILE) {
+        Ok(mut x) => {
+            if let Some(seed) = seed {
+                x.reseed(seed);
+            }
+            x
+        }
+        //Only a missing save starts afresh, anything else would be overwritten on the next save
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => match seed {
+            Some(seed) => Encounter::with_seed(seed),
+            None => Encounter::new(),
+        },
+        Err(e) => {
+            eprintln!("Unable to read encounter from {}: {}", SAVE_FILE, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+//What the program was asked to do on the command line
+#[derive(Default)]
+struct Options {
+    seed: Option<u64>,
+    export_log: bool,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = std::env::args().skip(1);
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--export-log" => options.export_log = true,
+            "--seed" => match args.next().map(|x| x.parse::<u64>()) {
+                Some(Ok(x)) => options.seed = Some(x),
+                _ => return Err(String::from("--seed requires a number")),
+            },
+            _ => return Err(format!("Unknown argument {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
 fn main() {
+    let options = match parse_args() {
+        Ok(x) => x,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    if options.export_log {
+        export_log();
+        return;
+    }
+    let encounter = load_encounter(options.seed);
+
     ncurses::initscr();
     ncurses::setlocale(ncurses::LcCategory::all, "");
     ncurses::noecho();
@@ -16,8 +89,9 @@ fn main() {
     ncurses::init_pair(Color::Green as i16, ncurses::COLOR_GREEN, 0);
     ncurses::init_pair(Color::Yellow as i16, ncurses::COLOR_YELLOW, 0);
     ncurses::init_pair(Color::Magenta as i16, ncurses::COLOR_MAGENTA, 0);
+    ncurses::init_pair(Color::Cyan as i16, ncurses::COLOR_CYAN, 0);
 
-    let mut window = MainWindow::new();
+    let mut window = MainWindow::new(encounter);
 
     loop {
         window.render();
@@ -1,47 +1,75 @@
-use crate::combat::{Character, Encounter, MonsterDB};
-use crate::textbox::{textbox_open, textbox_select};
+use crate::charms::{Charm, CharmLibrary, Duration};
+use crate::combat::{CharId, Character, CombatError, DamageType, Encounter, MonsterDB, Side, Stat};
+use crate::condition::Condition;
+use crate::essence::{Anima, Resource};
+use crate::event::CombatEvent;
+use crate::gambit::Gambit;
+use crate::grapple::GrappleAction;
+use crate::range::{Movement, Range};
+use crate::rules;
+use crate::textbox::{textbox_open, textbox_select, textbox_show};
 use crate::util::Color;
 use crate::util::Drawable;
 use crate::util::{drawcolor, drawtext};
 
-const HELPSTR: &str = "a.dd d.ecis w.ith r.emov i.nit o.nsl";
+const HELPSTR: &str = "a.dd d.ecis w.ith b.gam g.rap M.ove t.urn H.old p.ool c.harm C.ond r.emov i.nit o.nsl e.def s.ide f.ilt v.iew u.ndo";
 
 const KEY_UP: i32 = 'k' as i32;
 const KEY_DOWN: i32 = 'j' as i32;
 const KEY_NEW_ROUND: i32 = 'n' as i32;
+const KEY_NEXT_TURN: i32 = 't' as i32;
+const KEY_DELAY: i32 = 'H' as i32;
+const KEY_SIDE: i32 = 's' as i32;
+const KEY_FILTER: i32 = 'f' as i32;
+const KEY_SUMMARY: i32 = 'v' as i32;
+const KEY_SPEND: i32 = 'p' as i32;
+const KEY_CHARM: i32 = 'c' as i32;
+const KEY_CONDITION: i32 = 'C' as i32;
 const KEY_HEALTH: i32 = 'h' as i32;
 const KEY_ONSLAUGHT: i32 = 'o' as i32;
 const KEY_INITIATIVE: i32 = 'i' as i32;
+const KEY_DEFENSE_MODIFIER: i32 = 'e' as i32;
 const KEY_MARK_DONE: i32 = 'D' as i32;
 const KEY_ADD_CHAR: i32 = 'a' as i32;
 const KEY_ADD_MONSTER: i32 = 'm' as i32;
 const KEY_DECISIVE_ATTACK: i32 = 'd' as i32;
 const KEY_WITHERING_ATTACK: i32 = 'w' as i32;
+const KEY_GRAPPLE: i32 = 'g' as i32;
+const KEY_GAMBIT: i32 = 'b' as i32;
+const KEY_MOVE: i32 = 'M' as i32;
 const KEY_REMOVE: i32 = 'r' as i32;
 const KEY_RESET: i32 = 'x' as i32;
+const KEY_UNDO: i32 = 'u' as i32;
+const KEY_REDO: i32 = 18; // Ctrl-R
 const KEY_CANCEL: i32 = 27;
 
-macro_rules! log {
-    ($mand:expr, $($t:tt)*) => {
-        $mand.encounter.log(format!($($t)*));
-    };
+const MANUAL_ATTACK: &str = "Manual";
+
+pub const SAVE_FILE: &str = "/tmp/__exaltedcombat";
+
+//Reads an amount of damage like "3", "2B" or "-1L", lethal unless specified
+fn parse_damage(text: &str) -> Option<(i32, DamageType)> {
+    let amount = text.trim().trim_end_matches(|x: char| x.is_ascii_alphabetic());
+    let kind = DamageType::from_suffix(text).unwrap_or(DamageType::Lethal);
+    amount.parse::<i32>().ok().map(|x| (x, kind))
 }
 
-macro_rules! set_char_value {
-    ($self:ident, $name:literal, $title:literal, $val:ident) => {{
-        let char = &mut $self.get_selected_char_mut();
-        let result = textbox_open($title);
-        char.$val = result.parse::<i32>().unwrap_or(char.$val);
-    }
-    log!(
-        $self,
-        "{} {} set to {}. ",
-        $name,
-        $self.get_selected_char().name,
-        $self.get_selected_char().initiative
-    );
-    $self.encounter.update();
-    $self.save_char_list();};
+fn side_color(side: &Side) -> Color {
+    match side {
+        Side::Players => Color::Cyan,
+        Side::Enemies => Color::Red,
+        Side::Neutral => Color::White,
+        Side::Custom(_) => Color::Yellow,
+    }
+}
+
+fn anima_color(anima: Anima) -> Option<Color> {
+    match anima {
+        Anima::Dim => None,
+        Anima::Glowing => Some(Color::Yellow),
+        Anima::Burning => Some(Color::Red),
+        Anima::Bonfire => Some(Color::Magenta),
+    }
 }
 
 pub struct MainWindow {
@@ -52,21 +80,28 @@ pub struct MainWindow {
     markedpos: i32,
     message: Option<String>,
     action: Option<Action>,
+    filter: Option<Side>,
     encounter: Encounter,
     monsterdb: MonsterDB,
+    charmdb: CharmLibrary,
 }
 
 #[derive(Clone)]
 struct Action {
-    position: i32,
+    attacker: CharId,
+}
+
+enum AttackChoice {
+    Manual,
+    Rolled(usize),
 }
 
 impl Default for MainWindow {
-    fn default() -> Self { Self::new() }
+    fn default() -> Self { Self::new(Encounter::new()) }
 }
 
 impl MainWindow {
-    pub fn new() -> MainWindow {
+    pub fn new(encounter: Encounter) -> MainWindow {
         let midw = ncurses::COLS() / 2;
         let midh = ncurses::LINES() / 2;
 
@@ -74,45 +109,105 @@ impl MainWindow {
             leftwin: ncurses::subwin(ncurses::stdscr(), midh, midw, 0, 0),
             rightwin: ncurses::subwin(ncurses::stdscr(), ncurses::LINES(), midw, 0, midw),
             logwin: ncurses::subwin(ncurses::stdscr(), midh, midw, midh, 0),
-            encounter: Encounter::new(),
+            encounter,
             monsterdb: MonsterDB::load(),
+            charmdb: CharmLibrary::load(),
             selpos: 1,
             markedpos: -1,
             message: None,
             action: None,
+            filter: None,
         };
-        window.load_char_list();
         window.encounter.update();
         window
     }
 
     fn cursor_move(&mut self, amount: i32) {
         self.selpos += amount;
-        if self.selpos > self.encounter.charcount() as i32 {
-            self.selpos = self.encounter.charcount() as i32;
+        if self.selpos > self.visible().len() as i32 {
+            self.selpos = self.visible().len() as i32;
         } else if self.selpos < 1 {
             self.selpos = 1
         }
     }
 
-    fn mark_done(&mut self) {
-        self.get_selected_char_mut().done ^= true;
-        log!(self, "{} finished his turn. ", self.get_selected_char().name);
-        self.encounter.update();
-        self.save_char_list();
+    fn selected(&self) -> CharId {
+        self.get_selected_char().id
     }
 
-    fn get_char_by_index(&self, index: i32) -> &Character {
-        match self.encounter.char_at(index as usize - 1) {
-            Some(x) => x,
-            None => {
-                panic!("Selected character out of bounds");
+    //Shows engine errors in the message line and saves after successful actions
+    fn handle(&mut self, result: Result<(), CombatError>) {
+        match result {
+            Ok(()) => {
+                self.save_char_list();
+                self.check_over();
             }
+            Err(e) => self.message = Some(e.to_string()),
+        }
+    }
+
+    //Offers the summary screen when the last action ended the combat
+    fn check_over(&mut self) {
+        if let Some(CombatEvent::CombatOver { .. }) = self.encounter.log_iter().last() {
+            if textbox_open("Combat over! Summary? Y/n").trim().to_lowercase().as_str() != "n" {
+                self.show_summary();
+            }
+        }
+    }
+
+    fn show_summary(&self) {
+        textbox_show("Combat Summary", &self.encounter.summary().lines());
+    }
+
+    fn mark_done(&mut self) {
+        let result = self.encounter.toggle_done(self.selected());
+        self.handle(result);
+    }
+
+    fn set_stat(&mut self, stat: Stat, title: &str) {
+        if let Ok(x) = textbox_open(title).parse::<i32>() {
+            let result = self.encounter.set_stat(self.selected(), stat, x);
+            self.handle(result);
         }
     }
 
-    fn get_char_by_index_mut(&mut self, index: i32) -> &mut Character {
-        match self.encounter.char_at_mut(index as usize - 1) {
+    fn change_health(&mut self) {
+        if let Some((amount, kind)) = parse_damage(&textbox_open("Damage (2L, -1B heals): ")) {
+            let result = self.encounter.change_health(self.selected(), amount, kind);
+            self.handle(result);
+        }
+    }
+
+    //Characters shown in the participant list, everyone unless filtered by side
+    fn visible(&self) -> Vec<&Character> {
+        let chars: Vec<&Character> = self
+            .encounter
+            .char_iter()
+            .filter(|x| self.filter.as_ref().map_or(true, |y| x.side == *y))
+            .collect();
+        if chars.is_empty() {
+            self.encounter.char_iter().collect()
+        } else {
+            chars
+        }
+    }
+
+    //Moves the cursor to a character, clearing the filter if they are hidden
+    fn select(&mut self, id: CharId) {
+        if !self.visible().iter().any(|x| x.id == id) {
+            self.filter = None;
+        }
+        if let Some(x) = self.visible().iter().position(|x| x.id == id) {
+            self.selpos = x as i32 + 1;
+        }
+    }
+
+    fn name_of(&self, id: CharId) -> String {
+        self.encounter.char_by_id(id).map_or(String::from("None"), |x| x.display_name())
+    }
+
+    fn get_char_by_index(&self, index: i32) -> &Character {
+        match self.visible().get(index as usize - 1) {
             Some(x) => x,
             None => {
                 panic!("Selected character out of bounds");
@@ -124,35 +219,149 @@ impl MainWindow {
         self.get_char_by_index(self.selpos)
     }
 
-    fn get_action_source(&self, action: &Action) -> &Character {
-        self.get_char_by_index(action.position)
+    fn new_round(&mut self) {
+        if textbox_open("New Round? y/N").as_str() == "y" {
+            self.encounter.new_round();
+            self.save_char_list();
+        }
     }
 
-    fn get_selected_char_mut(&mut self) -> &mut Character {
-        self.get_char_by_index_mut(self.selpos)
+    fn next_turn(&mut self) {
+        match self.encounter.next_turn() {
+            Some(id) => self.select(id),
+            None => self.message = Some(String::from("Nobody is able to act")),
+        }
+        self.save_char_list();
+        self.check_over();
     }
 
-    fn get_action_source_mut(&mut self, action: &Action) -> &mut Character {
-        self.get_char_by_index_mut(action.position)
+    fn delay(&mut self) {
+        let id = self.selected();
+        let result = if self.get_selected_char().delayed {
+            self.encounter.resume(id)
+        } else {
+            self.encounter.delay(id)
+        };
+        self.handle(result);
     }
 
-    fn new_round(&mut self) {
-        if textbox_open("New Round? y/N").as_str() == "y" {
-            log!(self, "New round! ");
-            self.encounter.new_round();
-            self.save_char_list();
+    fn spend(&mut self) {
+        let names: Vec<String> = Resource::all().iter().map(|x| x.to_string()).collect();
+        let selected = textbox_select("Spend: ", &names.iter().map(|x| x.as_str()).collect());
+        let resource = match Resource::all().into_iter().find(|x| x.to_string() == selected) {
+            Some(x) => x,
+            None => return,
+        };
+        if let Ok(x) = textbox_open("Amount (negative regains): ").trim().parse::<i32>() {
+            let result = self.encounter.spend(self.selected(), resource, x);
+            self.handle(result);
+        }
+    }
+
+    //Activates a charm, or ends one of the character's active effects
+    fn activate_charm(&mut self) {
+        let char = self.get_selected_char();
+        let charms: Vec<Charm> = char
+            .charms
+            .iter()
+            .flatten()
+            .chain(self.charmdb.charms())
+            .cloned()
+            .collect();
+        let effects: Vec<String> = char.effects.iter().map(|x| x.name.clone()).collect();
+        if charms.is_empty() && effects.is_empty() {
+            self.message = Some(String::from("No charms available"));
+            return;
+        }
+        let mut names: Vec<String> =
+            charms.iter().map(|x| format!("{} ({})", x.name, x.cost)).collect();
+        names.extend(effects.iter().map(|x| format!("End {}", x)));
+        let selected = textbox_select("Charm: ", &names.iter().map(|x| x.as_str()).collect());
+        if let Some(x) = selected.strip_prefix("End ").filter(|x| effects.iter().any(|y| y == x)) {
+            let result = self.encounter.end_effect(self.selected(), x);
+            self.handle(result);
+        } else if let Some(x) = names.iter().position(|x| *x == selected) {
+            let result = self.encounter.activate_charm(self.selected(), &charms[x]);
+            self.handle(result);
+        }
+    }
+
+    //Adds a common or custom condition, or removes one the character already has
+    fn condition(&mut self) {
+        let current: Vec<String> =
+            self.get_selected_char().conditions.iter().map(|x| x.name.clone()).collect();
+        let mut names: Vec<String> = Condition::presets().into_iter().map(|x| x.name).collect();
+        names.push(String::from("Custom"));
+        names.extend(current.iter().map(|x| format!("Remove {}", x)));
+        let selected = textbox_select("Condition: ", &names.iter().map(|x| x.as_str()).collect());
+        if let Some(x) = selected.strip_prefix("Remove ").filter(|x| current.iter().any(|y| y == x))
+        {
+            let result = self.encounter.remove_condition(self.selected(), x);
+            self.handle(result);
+            return;
+        }
+        let condition = match Condition::presets().into_iter().find(|x| x.name == selected) {
+            Some(x) => x,
+            None if selected == "Custom" => {
+                let name = textbox_open("Name: ");
+                if name.trim().is_empty() {
+                    return;
+                }
+                let defense = textbox_open("Defense modifier: ").trim().parse::<i32>().unwrap_or(0);
+                let pools = textbox_open("Dice pool modifier: ").trim().parse::<i32>().unwrap_or(0);
+                Condition::new(name.trim(), defense, pools)
+            }
+            None => return,
+        };
+        match Duration::parse(&textbox_open("Duration (2t, 3r, scene, blank lasts): ")) {
+            Some(x) => {
+                let id = self.selected();
+                let result = self.encounter.add_condition(id, condition.with_duration(x));
+                self.handle(result);
+            }
+            None => self.message = Some(String::from("Invalid duration")),
         }
     }
 
+    fn set_side(&mut self) {
+        let side = match textbox_select("Side: ", &vec!["Players", "Enemies", "Neutral", "Custom"])
+            .as_str()
+        {
+            "Players" => Side::Players,
+            "Enemies" => Side::Enemies,
+            "Neutral" => Side::Neutral,
+            "Custom" => match textbox_open("Side name: ") {
+                x if x.is_empty() => return,
+                x => Side::Custom(x),
+            },
+            _ => return,
+        };
+        let result = self.encounter.set_side(self.selected(), side);
+        self.cursor_move(0);
+        self.handle(result);
+    }
+
+    fn cycle_filter(&mut self) {
+        let sides = self.encounter.sides();
+        self.filter = match &self.filter {
+            None => sides.first().cloned(),
+            Some(x) => sides.iter().skip_while(|y| *y != x).nth(1).cloned(),
+        };
+        self.message = Some(match &self.filter {
+            Some(x) => format!("Showing {}", x),
+            None => String::from("Showing everyone"),
+        });
+        self.selpos = 1;
+    }
+
     fn add_char(&mut self) {
         let name = textbox_open("Name: ");
         if name.is_empty() {
             return;
         }
         let joinbattle = textbox_open("Join Battle Dice: ");
-        log!(self, "{} joined combat! ", name);
         let char = Character::new(name, joinbattle.parse::<i32>().unwrap_or(0), 7);
-        self.encounter.add_char(char);
+        self.encounter.join_combat(char);
         self.save_char_list();
     }
 
@@ -164,98 +373,203 @@ impl MainWindow {
         let label = char::from_u32(self.encounter.count_name(selmonster.as_str()) as u32 + 65);
         if let Some(mut x) = self.monsterdb.get_monster_by_name(selmonster.as_str()) {
             x.label = label;
-            self.encounter.add_char(x);
-            log!(self, "{} {} joined combat! ", selmonster, label.unwrap_or(' '));
+            self.encounter.join_combat(x);
             self.save_char_list();
         }
     }
 
     fn select_target(&mut self) {
         self.action = Some(Action {
-            position: self.selpos,
+            attacker: self.selected(),
         });
     }
 
     fn decisive_attack(&mut self, action: &Action) {
-        if self.get_action_source(action).crashed() || self.get_action_source(action).dead() {
-            self.message = Some(("Crashed/Dead character cannot decisive attack").to_string());
-            return;
-        }
-        let hit = textbox_open("Hit (dmg/N)?").trim().to_lowercase();
-        if hit == "n" {
-            self.get_action_source_mut(action).do_decisive_miss();
-            log!(
-                self,
-                "{} misses decisive attack on {}. ",
-                self.get_action_source(action).name,
-                self.get_selected_char().name
-            );
-        } else {
-            if let Ok(x) = hit.parse::<i32>() {
-                self.get_action_source_mut(action).do_decisive_hit();
-                self.get_selected_char_mut().take_decisive_hit(x);
-                log!(
-                    self,
-                    "{} hits decisive attack on {}, with {} damage!",
-                    self.get_action_source(action).name,
-                    self.get_selected_char().name,
-                    x
-                );
+        let attacker = action.attacker;
+        let result = match self.choose_attack(action) {
+            None => Ok(()),
+            Some(AttackChoice::Manual) => {
+                match textbox_open("Hit (dmg[B/L/A]/N)?").trim().to_lowercase().as_str() {
+                    "n" => self
+                        .encounter
+                        .decisive_attack(attacker, self.selected(), None, DamageType::Lethal)
+                        .map(|_| ()),
+                    x => match parse_damage(x) {
+                        Some((damage, kind)) => self
+                            .encounter
+                            .decisive_attack(attacker, self.selected(), Some(damage), kind)
+                            .map(|_| ()),
+                        None => Ok(()),
+                    },
+                }
+            }
+            Some(AttackChoice::Rolled(attack)) => self
+                .encounter
+                .rolled_decisive_attack(attacker, attack, self.selected())
+                .map(|_| ()),
+        };
+        self.handle(result);
+        self.cancel();
+    }
+
+    fn gambit(&mut self, action: &Action) {
+        let names: Vec<String> = Gambit::all().iter().map(|x| x.to_string()).collect();
+        let selected = textbox_select("Gambit: ", &names.iter().map(|x| x.as_str()).collect());
+        let gambit = match Gambit::all().into_iter().find(|x| x.to_string() == selected) {
+            Some(x) => x,
+            None => return self.cancel(),
+        };
+        let attacker = action.attacker;
+        let result = match self.choose_attack(action) {
+            None => Ok(()),
+            Some(AttackChoice::Manual) => {
+                match textbox_open("Initiative roll successes (N misses): ").trim() {
+                    "n" | "N" => {
+                        self.encounter.gambit(attacker, self.selected(), gambit, None).map(|_| ())
+                    }
+                    x => match x.parse::<i32>() {
+                        Ok(x) => self
+                            .encounter
+                            .gambit(attacker, self.selected(), gambit, Some(x))
+                            .map(|_| ()),
+                        Err(_) => Ok(()),
+                    },
+                }
+            }
+            Some(AttackChoice::Rolled(attack)) => self
+                .encounter
+                .rolled_gambit(attacker, attack, self.selected(), gambit)
+                .map(|_| ()),
+        };
+        self.handle(result);
+        self.cancel();
+    }
+
+    //Moves the marked character relative to the selected one
+    fn move_char(&mut self, action: &Action) {
+        let choices = vec!["Closer", "Farther", "Rush", "Disengage", "Set range"];
+        let success = || textbox_open("Success? y/N").trim().to_lowercase() == "y";
+        let movement = match textbox_select("Move: ", &choices).as_str() {
+            "Closer" => Movement::Closer,
+            "Farther" => Movement::Farther,
+            "Rush" => Movement::Rush(success()),
+            "Disengage" => Movement::Disengage(success()),
+            "Set range" => {
+                let names: Vec<String> = Range::all().iter().map(|x| x.to_string()).collect();
+                let range = textbox_select("Range: ", &names.iter().map(|x| x.as_str()).collect());
+                match Range::all().into_iter().find(|x| x.to_string() == range) {
+                    Some(x) => Movement::Set(x),
+                    None => return self.cancel(),
+                }
             }
+            _ => return self.cancel(),
+        };
+        let result = self.encounter.move_char(action.attacker, self.selected(), movement);
+        self.handle(result.map(|_| ()));
+        self.cancel();
+    }
+
+    //Grapples are gambits, the rounds of control are only asked for once the gambit lands
+    fn grapple(&mut self, action: &Action) {
+        let roll = match textbox_open("Initiative roll successes (N misses): ").trim() {
+            "n" | "N" => None,
+            x => match x.parse::<i32>() {
+                Ok(x) => Some(x),
+                Err(_) => return self.cancel(),
+            },
+        };
+        let mut control = 0;
+        if roll.is_some_and(|x| rules::gambit_succeeds(x, Gambit::Grapple.difficulty())) {
+            control = match textbox_open("Control rounds: ").trim().parse::<i32>() {
+                Ok(x) => x,
+                Err(_) => return self.cancel(),
+            };
         }
-        self.encounter.update();
-        self.save_char_list();
+        let result = self.encounter.grapple(action.attacker, self.selected(), roll, control);
+        self.handle(result.map(|_| ()));
         self.cancel();
     }
 
-    fn withering_attack(&mut self, action: &Action) {
-        if self.get_action_source(action).dead() {
-            self.message = Some(("Dead character cannot withering attack").to_string());
-            return;
+    //Actions for a character already in a grapple, the grappler uses the clinch and the
+    //victim can only try to break free
+    fn grapple_menu(&mut self) {
+        let id = self.selected();
+        let mut names: Vec<String> = Vec::new();
+        if self.encounter.grapple_of(id).is_some_and(|x| x.grappler == id) {
+            names.extend(GrappleAction::all().iter().map(|x| x.to_string()));
+            names.push(String::from("Release"));
+        } else {
+            names.push(String::from("Escape"));
         }
-        if let Ok(x) = textbox_open("Damage (-1: miss)").parse::<i32>() {
-            let attackername = self.get_action_source(action).name.clone();
-            let crashed = self
-                .get_selected_char_mut()
-                .take_withering_hit(attackername, x);
-            self.get_action_source_mut(action)
-                .do_withering_hit(x, crashed);
-            if x == -1 {
-                log!(
-                    self,
-                    "{} misses withering attack on {}. ",
-                    self.get_action_source(action).name,
-                    self.get_selected_char().name
-                );
-            } else {
-                log!(
-                    self,
-                    "{} hits a withering attack on {} for {} damage. ",
-                    self.get_action_source(action).name,
-                    self.get_selected_char().name,
-                    x
-                );
+        let selected = textbox_select("Grapple: ", &names.iter().map(|x| x.as_str()).collect());
+        let result = match GrappleAction::all().into_iter().find(|x| x.to_string() == selected) {
+            Some(GrappleAction::Savage) => {
+                let text = textbox_open("Damage (3 withering, 2L decisive): ");
+                match text.trim().parse::<i32>() {
+                    Ok(x) => self.encounter.savage(id, x, None).map(|_| ()),
+                    Err(_) => match parse_damage(&text) {
+                        Some((x, kind)) => self.encounter.savage(id, x, Some(kind)).map(|_| ()),
+                        None => return,
+                    },
+                }
+            }
+            Some(x) => self.encounter.grapple_action(id, x),
+            None if selected == "Release" => self.encounter.release(id),
+            None if selected == "Escape" => {
+                let success = textbox_open("Won the contested roll? y/N").trim().to_lowercase();
+                self.encounter.escape(id, success == "y")
             }
+            None => return,
+        };
+        self.handle(result);
+    }
+
+    fn choose_attack(&self, action: &Action) -> Option<AttackChoice> {
+        let source = self.encounter.char_by_id(action.attacker);
+        let attacks = match source.and_then(|x| x.attacks.as_ref()) {
+            Some(x) if !x.is_empty() => x,
+            _ => return Some(AttackChoice::Manual),
+        };
+        let mut names: Vec<&str> = attacks.iter().map(|x| x.name.as_str()).collect();
+        names.push(MANUAL_ATTACK);
+        let selected = textbox_select("Attack: ", &names);
+        if selected.is_empty() {
+            return None;
         }
-        self.encounter.update();
-        self.save_char_list();
+        match attacks.iter().position(|x| x.name == selected) {
+            Some(x) => Some(AttackChoice::Rolled(x)),
+            None => Some(AttackChoice::Manual),
+        }
+    }
+
+    fn withering_attack(&mut self, action: &Action) {
+        let attacker = action.attacker;
+        let result = match self.choose_attack(action) {
+            None => Ok(()),
+            Some(AttackChoice::Manual) => match textbox_open("Damage (-1: miss)").parse::<i32>() {
+                Ok(x) => self
+                    .encounter
+                    .withering_attack(attacker, self.selected(), if x < 0 { None } else { Some(x) })
+                    .map(|_| ()),
+                Err(_) => Ok(()),
+            },
+            Some(AttackChoice::Rolled(attack)) => self
+                .encounter
+                .rolled_withering_attack(attacker, attack, self.selected())
+                .map(|_| ()),
+        };
+        self.handle(result);
         self.cancel();
     }
 
     fn remove_char(&mut self) {
-        if self.encounter.charcount() <= 1 {
-            self.message = Some(String::from("Cannot remove last character"));
-            return;
-        }
         let text = format!("Remove {}? y/N", self.get_selected_char().name);
         if textbox_open(text.as_str()).trim().to_lowercase().as_str() != "y" {
             return;
         }
-        self.encounter.removechar(self.selpos as usize - 1);
-        if self.selpos as usize > self.encounter.charcount() {
-            self.selpos = self.encounter.charcount() as i32;
-        }
-        self.save_char_list();
+        let result = self.encounter.remove_char(self.selected());
+        self.cursor_move(0);
+        self.handle(result);
     }
 
     fn reset(&mut self) {
@@ -264,46 +578,50 @@ impl MainWindow {
         }
     }
 
-    fn cancel(&mut self) {
-        self.action = None;
+    fn undo(&mut self) {
+        if self.encounter.undo() {
+            self.cursor_move(0);
+            self.save_char_list();
+        } else {
+            self.message = Some(String::from("Nothing to undo"));
+        }
     }
 
-    fn load_char_list(&mut self) {
-        self.encounter = match std::fs::read_to_string("/tmp/__exaltedcombat") {
-            Ok(x) => match serde_json::from_str(&x) {
-                Ok(y) => y,
-                Err(e) => {
-                    panic!("Error parsing previous encounter state {}", e);
-                }
-            },
-            Err(_) => Encounter::new(),
-        };
+    fn redo(&mut self) {
+        if self.encounter.redo() {
+            self.cursor_move(0);
+            self.save_char_list();
+        } else {
+            self.message = Some(String::from("Nothing to redo"));
+        }
+    }
+
+    fn cancel(&mut self) {
+        self.action = None;
     }
 
     fn save_char_list(&mut self) {
-        match serde_json::to_string(&self.encounter) {
-            Ok(x) => match std::fs::write("/tmp/__exaltedcombat", x) {
-                Ok(_) => {}
-                Err(_) => {
-                    self.message = Some("Unable to save encounter to file".into());
-                }
-            },
-            Err(_) => {
-                self.message = Some("Error parsing encounter to json".into());
-            }
+        if self.encounter.save(SAVE_FILE).is_err() {
+            self.message = Some("Unable to save encounter to file".into());
         }
     }
 
     fn draw_char_list(&self) {
         ncurses::werase(self.leftwin);
         ncurses::wborder(self.leftwin, 32, 32, 0, 32, 0, 0, 0, 0);
-        drawtext(self.leftwin, 0, 2, "Participants", Color::White, true, true, false, false, 32);
-        for(pos, char) in (1_i32..).zip(self.encounter.char_iter()) {
+        let title = match &self.filter {
+            Some(x) => format!("Participants - Round {} - {}", self.encounter.round(), x),
+            None => format!("Participants - Round {}", self.encounter.round()),
+        };
+        drawtext(self.leftwin, 0, 2, &title, Color::White, true, true, false, false, 32);
+        for (pos, char) in (1_i32..).zip(self.visible()) {
             let color = if self.markedpos == pos - 1 {
                 Color::Blue
-            } else if self.action.is_some() && self.action.as_ref().unwrap().position == pos {
+            } else if self.action.as_ref().is_some_and(|x| x.attacker == char.id) {
                 Color::Magenta
-            } else if char.dead() {
+            } else if self.encounter.current() == Some(char.id) {
+                Color::Green
+            } else if char.incapacitated() {
                 Color::Red
             } else if char.crashed() {
                 Color::Yellow
@@ -311,19 +629,30 @@ impl MainWindow {
                 Color::White
             };
 
+            let side = char.side.to_string().chars().next().unwrap_or(' ').to_string();
+            drawtext(self.leftwin, pos, 1, &side, side_color(&char.side), true, false, false, false, 1);
             drawtext(
                 self.leftwin,
                 pos,
                 2,
                 format!(
-                    "{:<width$}{:<4}{:<4}{:<2}{:<2}{:<6}",
+                    "{:<width$}{:<4}{:<4}{:<4}{:<2}{:<2}{:<12}",
                     format!("{} {}", char.name, char.label.unwrap_or(' ')),
+                    char.conditions.iter().map(|x| x.icon()).take(3).collect::<String>(),
                     char.initiative,
                     char.onslaught,
-                    if char.done { "D" } else { "" },
+                    if char.done {
+                        "D"
+                    } else if char.extra_turn {
+                        "S"
+                    } else if char.delayed {
+                        "H"
+                    } else {
+                        ""
+                    },
                     if char.crashed() { "C" } else { "" },
-                    format!("{}/{}", char.health, char.maxhealth),
-                    width = (ncurses::COLS() / 2 - 23) as usize
+                    char.health_status(),
+                    width = (ncurses::COLS() / 2 - 33) as usize
                 )
                 .as_str(),
                 color,
@@ -333,6 +662,10 @@ impl MainWindow {
                 char.done,
                 ncurses::COLS() / 2,
             );
+            if let Some(x) = anima_color(char.anima) {
+                let col = ncurses::COLS() / 2 - 2;
+                drawtext(self.leftwin, pos, col, "*", x, true, false, false, false, 1);
+            }
         }
 
         drawcolor(
@@ -367,7 +700,7 @@ impl MainWindow {
             self.rightwin,
             2,
             2,
-            format!("Evasion: {}", char.evasion).as_str(),
+            format!("Evasion: {} ({})", char.effective_evasion(), char.evasion).as_str(),
             Color::Blue,
             ncurses::COLS() / 4 - 2,
         );
@@ -375,7 +708,7 @@ impl MainWindow {
             self.rightwin,
             2,
             ncurses::COLS() / 4 - 1,
-            format!("Parry:    {}", char.parry).as_str(),
+            format!("Parry:    {} ({})", char.effective_parry(), char.parry).as_str(),
             Color::Blue,
             ncurses::COLS() / 4 - 2,
         );
@@ -407,26 +740,142 @@ impl MainWindow {
             self.rightwin,
             4,
             ncurses::COLS() / 4 - 1,
-            format!("Crasher:  {}", char.crasher_name.as_ref().unwrap_or(&String::from("None")))
-                .as_str(),
+            format!(
+                "Crasher:  {}",
+                char.crasher.map_or(String::from("None"), |x| self.name_of(x))
+            )
+            .as_str(),
             Color::Blue,
             ncurses::COLS() / 4 - 2,
         );
+        let track: Vec<String> = (0..char.health.levels())
+            .map(|x| {
+                format!(
+                    "{}{}",
+                    char.health.penalty(x).map_or("I".to_string(), |y| format!("-{}", -y)),
+                    char.health.level(x).map_or(' ', |y| y.suffix())
+                )
+            })
+            .collect();
+        let health = match &char.battle_group {
+            Some(x) => format!(
+                "Size {} ({:?}) Might {} Magnitude {}/{}{}",
+                x.size,
+                x.drill,
+                x.might,
+                x.magnitude,
+                x.max_magnitude(char.health.levels()),
+                if x.routed { " Routed" } else { "" }
+            ),
+            None => format!("Health: {} ({})", track.join(" "), char.wound_penalty()),
+        };
+        drawcolor(
+            self.rightwin,
+            5,
+            2,
+            health.as_str(),
+            Color::Blue,
+            ncurses::COLS() / 2 - 3,
+        );
+
+        drawcolor(
+            self.rightwin,
+            6,
+            2,
+            format!(
+                "Essence: {}  Motes: {} {}  Willpower: {}  Anima: {}",
+                char.essence, char.personal, char.peripheral, char.willpower, char.anima
+            )
+            .as_str(),
+            Color::Blue,
+            ncurses::COLS() / 2 - 3,
+        );
 
-        let mut pos = 5;
+        let mut pos = 7;
         if let Some(attacks) = &char.attacks {
             for attack in attacks.iter() {
                 drawcolor(
                     self.rightwin,
                     pos,
                     2,
-                    format!("{}: {}d -> {}", attack.name, attack.dice, attack.damage).as_str(),
+                    format!(
+                        "{}: {}d -> {}{}",
+                        attack.name,
+                        attack.dice,
+                        attack.damage,
+                        attack.range.map_or(String::new(), |x| format!(" ({})", x))
+                    )
+                    .as_str(),
                     Color::Red,
                     ncurses::COLS() / 2 - 1,
                 );
                 pos += 1;
             }
         }
+        if let Some(charms) = &char.charms {
+            for charm in charms.iter() {
+                drawcolor(
+                    self.rightwin,
+                    pos,
+                    2,
+                    format!("{}: {} {:?}, {}", charm.name, charm.cost, charm.kind, charm.duration)
+                        .as_str(),
+                    Color::Magenta,
+                    ncurses::COLS() / 2 - 1,
+                );
+                pos += 1;
+            }
+        }
+        if !char.effects.is_empty() {
+            let effects: Vec<String> = char.effects.iter().map(|x| x.to_string()).collect();
+            drawcolor(
+                self.rightwin,
+                pos,
+                2,
+                format!("Active: {}", effects.join(", ")).as_str(),
+                Color::Green,
+                ncurses::COLS() / 2 - 1,
+            );
+            pos += 1;
+        }
+        let ranges: Vec<String> = self
+            .encounter
+            .char_iter()
+            .filter(|x| x.id != char.id)
+            .map(|x| (x.display_name(), self.encounter.range(char.id, x.id)))
+            .map(|(name, range)| format!("{} {}", name, range.abbrev()))
+            .collect();
+        if !ranges.is_empty() {
+            drawcolor(
+                self.rightwin,
+                pos,
+                2,
+                format!("Range: {}", ranges.join(", ")).as_str(),
+                Color::Cyan,
+                ncurses::COLS() / 2 - 1,
+            );
+            pos += 1;
+        }
+        if let Some(x) = self.encounter.grapple_of(char.id) {
+            let text = match x.grappler == char.id {
+                true => format!("Grappling {} ({} control)", self.name_of(x.victim), x.control),
+                false => format!("Held by {} ({} control)", self.name_of(x.grappler), x.control),
+            };
+            drawcolor(self.rightwin, pos, 2, &text, Color::Yellow, ncurses::COLS() / 2 - 1);
+            pos += 1;
+        }
+        if !char.conditions.is_empty() {
+            let conditions: Vec<String> = char.conditions.iter().map(|x| x.to_string()).collect();
+            drawcolor(
+                self.rightwin,
+                pos,
+                2,
+                format!("Conditions: {}", conditions.join(", ")).as_str(),
+                Color::Yellow,
+                ncurses::COLS() / 2 - 1,
+            );
+            pos += 1;
+        }
         pos += 1;
 
         if let Some(specials) = &char.specials {
@@ -481,7 +930,7 @@ impl MainWindow {
                 self.logwin,
                 idx as i32 + 1,
                 2,
-                msg.as_str(),
+                msg.to_string().as_str(),
                 Color::White,
                 ncurses::COLS() / 2 - 4,
             );
@@ -519,15 +968,26 @@ impl Drawable for MainWindow {
             ncurses::KEY_DOWN => self.cursor_move(1),
             KEY_MARK_DONE => self.mark_done(),
             KEY_INITIATIVE => {
-                set_char_value!(self, "initiative", "Initiative: ", initiative);
+                self.set_stat(Stat::Initiative, "Initiative: ");
             }
             KEY_ONSLAUGHT => {
-                set_char_value!(self, "onslaught", "Onslaught: ", onslaught);
+                self.set_stat(Stat::Onslaught, "Onslaught: ");
+            }
+            KEY_DEFENSE_MODIFIER => {
+                self.set_stat(Stat::DefenseModifier, "Defense modifier: ");
             }
             KEY_HEALTH => {
-                set_char_value!(self, "health", "Health: ", health);
+                self.change_health();
             }
             KEY_NEW_ROUND => self.new_round(),
+            KEY_NEXT_TURN => self.next_turn(),
+            KEY_DELAY => self.delay(),
+            KEY_SIDE => self.set_side(),
+            KEY_FILTER => self.cycle_filter(),
+            KEY_SUMMARY => self.show_summary(),
+            KEY_SPEND => self.spend(),
+            KEY_CHARM => self.activate_charm(),
+            KEY_CONDITION => self.condition(),
             KEY_ADD_CHAR => self.add_char(),
             KEY_ADD_MONSTER => self.add_monster(),
             KEY_DECISIVE_ATTACK => match self.action.clone() {
@@ -538,8 +998,25 @@ impl Drawable for MainWindow {
                 Some(x) => self.withering_attack(&x),
                 None => self.select_target(),
             },
+            KEY_GAMBIT => match self.action.clone() {
+                Some(x) => self.gambit(&x),
+                None => self.select_target(),
+            },
+            KEY_MOVE => match self.action.clone() {
+                Some(x) => self.move_char(&x),
+                None => self.select_target(),
+            },
+            KEY_GRAPPLE => match self.action.clone() {
+                Some(x) => self.grapple(&x),
+                None if self.encounter.grapple_of(self.selected()).is_some() => {
+                    self.grapple_menu()
+                }
+                None => self.select_target(),
+            },
             KEY_REMOVE => self.remove_char(),
             KEY_RESET => self.reset(),
+            KEY_UNDO => self.undo(),
+            KEY_REDO => self.redo(),
             KEY_CANCEL => self.cancel(),
             _ => {}
         }
@@ -0,0 +1,99 @@
+use crate::combat::CharId;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+//Distance between two combatants
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Range {
+    #[default]
+    Close,
+    Short,
+    Medium,
+    Long,
+    Extreme,
+}
+
+impl Range {
+    pub fn all() -> [Range; 5] {
+        [Range::Close, Range::Short, Range::Medium, Range::Long, Range::Extreme]
+    }
+
+    pub fn closer(self) -> Range {
+        match self {
+            Range::Extreme => Range::Long,
+            Range::Long => Range::Medium,
+            Range::Medium => Range::Short,
+            _ => Range::Close,
+        }
+    }
+
+    pub fn farther(self) -> Range {
+        match self {
+            Range::Close => Range::Short,
+            Range::Short => Range::Medium,
+            Range::Medium => Range::Long,
+            _ => Range::Extreme,
+        }
+    }
+
+    //Letter used in the details pane
+    pub fn abbrev(&self) -> char {
+        match self {
+            Range::Close => 'C',
+            Range::Short => 'S',
+            Range::Medium => 'M',
+            Range::Long => 'L',
+            Range::Extreme => 'X',
+        }
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Range::Close => write!(f, "close"),
+            Range::Short => write!(f, "short"),
+            Range::Medium => write!(f, "medium"),
+            Range::Long => write!(f, "long"),
+            Range::Extreme => write!(f, "extreme"),
+        }
+    }
+}
+
+//Ways of changing the range to another combatant
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Movement {
+    Closer,
+    Farther,
+    //Closes straight to close range on a success, a band otherwise
+    Rush(bool),
+    //Gets away from close range to short range on a success
+    Disengage(bool),
+    Set(Range),
+}
+
+//The range between one pair of combatants, pairs without one are at close range
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct RangeBand {
+    pub first: CharId,
+    pub second: CharId,
+    pub range: Range,
+}
+
+impl RangeBand {
+    pub fn new(a: CharId, b: CharId, range: Range) -> RangeBand {
+        RangeBand {
+            first: std::cmp::min(a, b),
+            second: std::cmp::max(a, b),
+            range,
+        }
+    }
+
+    pub fn between(&self, a: CharId, b: CharId) -> bool {
+        (self.first, self.second) == (std::cmp::min(a, b), std::cmp::max(a, b))
+    }
+
+    pub fn involves(&self, id: CharId) -> bool {
+        self.first == id || self.second == id
+    }
+}
@@ -0,0 +1,74 @@
+use crate::combat::Character;
+use crate::dice::RollResult;
+
+//Initiative gained for crashing an opponent
+pub const INITIATIVE_BREAK_BONUS: i32 = 5;
+
+//Turns a character spends crashed before recovering on their own
+pub const CRASH_RECOVERY_TURNS: i32 = 3;
+
+//Initiative a character recovers to after waiting out a crash
+pub const CRASH_RECOVERY_INITIATIVE: i32 = 3;
+
+//Motes every character regains at the start of a round
+pub const MOTE_RESPIRATION: i32 = 5;
+
+//Peripheral motes spent at once for each level the anima rises
+pub const MOTES_PER_ANIMA_LEVEL: i32 = 5;
+
+//Join Battle successes are added to this for a character's base initiative
+pub const BASE_INITIATIVE: i32 = 3;
+
+pub fn is_crashed(initiative: i32) -> bool {
+    initiative < 0
+}
+
+//True when a change in initiative takes a character into crash
+pub fn crashes(before: i32, after: i32) -> bool {
+    !is_crashed(before) && is_crashed(after)
+}
+
+//Bonus for a withering attack, only given when it crashed the defender
+pub fn initiative_break(crashed: bool) -> i32 {
+    if crashed {
+        INITIATIVE_BREAK_BONUS
+    } else {
+        0
+    }
+}
+
+//True if a character who ends their turn with this many crashed turns behind them recovers
+pub fn crash_recovers(crashed_turns: i32) -> bool {
+    crashed_turns + 1 >= CRASH_RECOVERY_TURNS
+}
+
+//Initiative from a Join Battle roll, never starting a character crashed
+pub fn base_initiative(join_battle: &RollResult) -> i32 {
+    std::cmp::max(join_battle.net() + BASE_INITIATIVE, 0)
+}
+
+//A crashed attacker crashing the character who crashed them shifts initiative
+pub fn initiative_shift(attacker: &Character, defender: &Character, crashed: bool) -> bool {
+    crashed && attacker.crashed() && attacker.crasher == Some(defender.id)
+}
+
+//Initiative after a shift, the attacker keeps what they had if that was higher
+pub fn shift_initiative(initiative: i32, join_battle: &RollResult) -> i32 {
+    std::cmp::max(initiative, base_initiative(join_battle))
+}
+
+//Anima levels a single spending of peripheral motes raises the banner by, smaller spends
+//don't add up over the round
+pub fn anima_levels(motes: i32) -> i32 {
+    motes / MOTES_PER_ANIMA_LEVEL
+}
+
+//Initiative paid for a successful gambit
+pub fn gambit_cost(difficulty: i32) -> i32 {
+    difficulty + 1
+}
+
+//A gambit works when the initiative roll meets its difficulty
+pub fn gambit_succeeds(successes: i32, difficulty: i32) -> bool {
+    successes >= difficulty
+}
@@ -0,0 +1,116 @@
+use crate::combat::{CharId, Encounter, Side};
+use crate::event::{CharRef, CombatEvent};
+use std::collections::HashMap;
+
+//What one character did over the course of an encounter
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CharacterSummary {
+    pub id: CharId,
+    pub name: String,
+    pub withering_dealt: i32,
+    pub withering_taken: i32,
+    pub damage_dealt: i32,
+    pub damage_taken: i32,
+    pub crashes: i32,
+    pub kills: i32,
+    pub standing: bool,
+}
+
+//End of combat statistics, computed from the encounter and its log
+#[derive(Debug, Clone, PartialEq)]
+pub struct CombatSummary {
+    pub rounds: u32,
+    pub winner: Option<Side>,
+    pub characters: Vec<CharacterSummary>,
+}
+
+impl CombatSummary {
+    pub fn new(encounter: &Encounter) -> CombatSummary {
+        let mut summary = CombatSummary {
+            rounds: encounter.round(),
+            winner: encounter.winner(),
+            characters: Vec::new(),
+        };
+        for char in encounter.char_iter() {
+            let entry = summary.entry(&char.char_ref());
+            entry.standing = !char.incapacitated();
+        }
+
+        //The last hit that could take someone out counts as the kill, withering hits only
+        //wear down battle groups
+        let mut last_hit: HashMap<CharId, CharRef> = HashMap::new();
+        for event in encounter.active_log() {
+            match event {
+                CombatEvent::WitheringHit { attacker, defender, damage, .. } => {
+                    summary.entry(attacker).withering_dealt += damage;
+                    summary.entry(defender).withering_taken += damage;
+                    let group = encounter.char_by_id(defender.id).map(|x| x.battle_group.is_some());
+                    if group == Some(true) {
+                        last_hit.insert(defender.id, attacker.clone());
+                    }
+                }
+                CombatEvent::DecisiveHit { attacker, defender, damage, .. } => {
+                    summary.entry(attacker).damage_dealt += damage;
+                    summary.entry(defender).damage_taken += damage;
+                    last_hit.insert(defender.id, attacker.clone());
+                }
+                CombatEvent::Crash { attacker, .. } => {
+                    summary.entry(attacker).crashes += 1;
+                }
+                _ => {}
+            }
+        }
+        for char in encounter.char_iter().filter(|x| x.incapacitated()) {
+            if let Some(x) = last_hit.get(&char.id) {
+                summary.entry(x).kills += 1;
+            }
+        }
+        summary
+    }
+
+    fn entry(&mut self, char: &CharRef) -> &mut CharacterSummary {
+        let index = match self.characters.iter().position(|x| x.id == char.id) {
+            Some(x) => x,
+            None => {
+                self.characters.push(CharacterSummary {
+                    id: char.id,
+                    name: char.name.clone(),
+                    ..Default::default()
+                });
+                self.characters.len() - 1
+            }
+        };
+        &mut self.characters[index]
+    }
+
+    pub fn character(&self, id: CharId) -> Option<&CharacterSummary> {
+        self.characters.iter().find(|x| x.id == id)
+    }
+
+    //Text for the summary screen, one line per character after the header
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            match &self.winner {
+                Some(x) => format!("{} won after {} rounds", x, self.rounds),
+                None => format!("Nobody won after {} rounds", self.rounds),
+            },
+            String::new(),
+            format!(
+                "{:<20}{:>10}{:>10}{:>8}{:>6}  {}",
+                "Name", "Withering", "Decisive", "Crashes", "Kills", "Status"
+            ),
+        ];
+        for char in &self.characters {
+            lines.push(format!(
+                "{:<20}{:>10}{:>10}{:>8}{:>6}  {}",
+                char.name,
+                format!("{}/{}", char.withering_dealt, char.withering_taken),
+                format!("{}/{}", char.damage_dealt, char.damage_taken),
+                char.crashes,
+                char.kills,
+                if char.standing { "Standing" } else { "Down" }
+            ));
+        }
+        lines
+    }
+}
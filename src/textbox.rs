@@ -11,7 +11,21 @@ pub fn textbox_open(title: &str) -> String {
 }
 
 pub fn textbox_select(title: &str, items: &Vec<&str>) -> String {
-    textbox_internal(title, Some(&items))
+    textbox_internal(title, Some(items))
+}
+
+//Shows lines of text over the whole screen until a key is pressed
+pub fn textbox_show(title: &str, lines: &[String]) {
+    let win = ncurses::subwin(ncurses::stdscr(), ncurses::LINES(), ncurses::COLS(), 0, 0);
+    ncurses::werase(win);
+    ncurses::wborder(win, 32, 32, 0, 32, 0, 0, 0, 0);
+    drawtext(win, 1, 1, title, Color::Yellow, true, true, false, false, ncurses::COLS() - 2);
+    for (pos, line) in (3..ncurses::LINES() - 1).zip(lines.iter()) {
+        drawcolor(win, pos, 1, line, Color::White, ncurses::COLS() - 2);
+    }
+    ncurses::wrefresh(win);
+    ncurses::getch();
+    ncurses::delwin(win);
 }
 
 fn textbox_internal(title: &str, items: Option<&Vec<&str>>) -> String {
@@ -26,9 +40,8 @@ fn textbox_internal(title: &str, items: Option<&Vec<&str>>) -> String {
 
         let mut pos = 3;
         let mut selvalue: Option<&str> = None;
-        if items.is_some() {
+        if let Some(items) = items {
             for item in items
-                .unwrap()
                 .iter()
                 .filter(|x| x.to_lowercase().contains(&text.to_lowercase()))
             {
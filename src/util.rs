@@ -1,5 +1,3 @@
-use rand::Rng;
-
 #[derive(Clone, Copy)]
 pub enum Color {
     White = 1,
@@ -8,6 +6,7 @@ pub enum Color {
     Green = 4,
     Yellow = 5,
     Magenta = 6,
+    Cyan = 7,
 }
 
 pub trait Drawable {
@@ -16,6 +15,7 @@ pub trait Drawable {
     fn process_events(&mut self, ch: i32);
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn drawtext(
     win: *mut i8, y: i32, x: i32, text: &str, color: Color, bold: bool, underline: bool,
     reverse: bool, dim: bool, len: i32,
@@ -54,26 +54,3 @@ pub fn drawtext(
 pub fn drawcolor(win: *mut i8, y: i32, x: i32, text: &str, color: Color, len: i32) {
     drawtext(win, y, x, text, color, false, false, false, false, len);
 }
-
-//Rolls an exalted die roll, ignoring 1s
-pub fn roll_dice(count: i32) -> i32 {
-    let mut result: i32 = 0;
-    let mut botches: i32 = 0;
-    let mut rng = rand::thread_rng();
-    for _ in 0..count {
-        result += match rng.gen_range(1..=10) {
-            7..=9 => 1,
-            10 => 2,
-            1 => {
-                botches += 1;
-                0
-            }
-            _ => 0,
-        };
-    }
-    if result > 0 {
-        return result;
-    } else {
-        return -botches;
-    }
-}
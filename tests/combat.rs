@@ -1,11 +1,23 @@
 #[allow(unused_imports)]
+use exalted_combat::battlegroup::*;
+use exalted_combat::charms::*;
 use exalted_combat::combat::*;
+use exalted_combat::condition::*;
+use exalted_combat::dice::DicePool;
+use exalted_combat::essence::{Anima, Pool, Resource};
+use exalted_combat::event::CombatEvent;
+use exalted_combat::gambit::Gambit;
+use exalted_combat::grapple::*;
+use exalted_combat::range::*;
+use exalted_combat::rules;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
 #[test]
 fn alive_and_dead_test() {
     let mut char = Character::new(String::from("Test"), 0, 1);
     assert!(!char.dead());
-    char.health = 0;
+    char.health.take(DamageType::Lethal, 1);
     assert!(char.dead());
 }
 
@@ -28,12 +40,12 @@ fn withering_attack_initiative() {
     defender.initiative = 5;
 
     //An attack with 0 damage, only +1i for attacker
-    attacker.do_withering_hit(0, defender.take_withering_hit("n".into(), 0));
+    attacker.do_withering_hit(0, defender.take_withering_hit(attacker.id, 0));
     assert_eq!(defender.initiative, 5);
     assert_eq!(attacker.initiative, 1 + 1);
 
     //An attack with 3 damage, +4i for attacker, -3i for defender
-    attacker.do_withering_hit(3, defender.take_withering_hit("n".into(), 3));
+    attacker.do_withering_hit(3, defender.take_withering_hit(attacker.id, 3));
     assert_eq!(defender.initiative, 2);
     assert_eq!(attacker.initiative, 2 + 1 + 3);
 }
@@ -45,7 +57,7 @@ fn withering_attack_crash_initiative() {
     attacker.initiative = 1;
     defender.initiative = 1;
 
-    attacker.do_withering_hit(2, defender.take_withering_hit("n".into(), 2));
+    attacker.do_withering_hit(2, defender.take_withering_hit(attacker.id, 2));
     assert_eq!(defender.initiative, -1);
     assert_eq!(attacker.initiative, 1 + 1 + 2 + 5);
 }
@@ -70,15 +82,15 @@ fn decisive_attack_hit() {
     defender.initiative = 5;
 
     attacker.do_decisive_hit();
-    defender.take_decisive_hit(3);
+    defender.take_decisive_hit(3, DamageType::Lethal);
     assert_eq!(attacker.initiative, 3);
-    assert_eq!(defender.health, 2);
+    assert_eq!(defender.health.remaining(), 2);
 
     attacker.initiative = 8;
     attacker.do_decisive_hit();
-    defender.take_decisive_hit(0);
+    defender.take_decisive_hit(0, DamageType::Lethal);
     assert_eq!(attacker.initiative, 3);
-    assert_eq!(defender.health, 2);
+    assert_eq!(defender.health.remaining(), 2);
 }
 
 #[test]
@@ -87,20 +99,45 @@ fn decisive_hit_hardness() {
 
     defender.initiative = 5;
     defender.hardness = 5;
-    defender.take_decisive_hit(3);
-    assert_eq!(defender.health, 5);
+    defender.take_decisive_hit(3, DamageType::Lethal);
+    assert_eq!(defender.health.remaining(), 5);
     
-    defender.take_decisive_hit(5);
-    assert_eq!(defender.health, 5);
+    defender.take_decisive_hit(5, DamageType::Lethal);
+    assert_eq!(defender.health.remaining(), 5);
 
-    defender.take_decisive_hit(6);
-    assert_eq!(defender.health, -1);
+    defender.take_decisive_hit(6, DamageType::Lethal);
+    assert_eq!(defender.health.remaining(), 0);
+    assert!(defender.dead());
 
     //Test if crashed characters have no hardness
     defender.initiative = -1;
-    defender.health = 5;
-    defender.take_decisive_hit(3);
-    assert_eq!(defender.health, 2);
+    defender.health.heal_all();
+    defender.take_decisive_hit(3, DamageType::Lethal);
+    assert_eq!(defender.health.remaining(), 2);
+}
+
+#[test]
+fn rolled_decisive_hardness() {
+    let mut attacker = Character::new(String::from("Attacker"), 3, 7);
+    attacker.attacks = Some(vec![Attack::new("Sword", 10, "12L")]);
+    let mut defender = Character::new(String::from("Defender"), 2, 7);
+    defender.hardness = 5;
+    let mut encounter = Encounter::from_characters(vec![attacker, defender], 3);
+    let (attacker, defender) = (id_of(&encounter, "Attacker"), id_of(&encounter, "Defender"));
+
+    //Initiative above hardness gets through even when fewer successes are rolled
+    encounter.set_stat(attacker, Stat::Initiative, 8).unwrap();
+    let outcome = encounter.rolled_decisive_attack(attacker, 0, defender).unwrap();
+    let successes = outcome.roll.as_ref().unwrap().damage.as_ref().unwrap().successes;
+    assert_eq!(successes, 2);
+    assert_eq!(outcome.damage, 2);
+    assert_eq!(encounter.char_by_id(defender).unwrap().health.damage(), 2);
+
+    //Initiative at hardness does nothing however many successes are rolled
+    encounter.set_stat(attacker, Stat::Initiative, 5).unwrap();
+    let outcome = encounter.rolled_decisive_attack(attacker, 0, defender).unwrap();
+    assert!(outcome.hit);
+    assert_eq!(outcome.damage, 0);
 }
 
 #[test]
@@ -118,18 +155,1444 @@ fn timed_initiative_reset() {
 fn initiative_shift() {
     let mut attacker = Character::new(String::from("Attacker"), 0, 1);
     let mut defender = Character::new(String::from("Defender"), 0, 1);
+    attacker.id = 1;
+    defender.id = 2;
     attacker.initiative = 1;
     defender.initiative = 1;
 
     attacker.do_withering_hit(3, true);
-    defender.take_withering_hit("Attacker".into(), 3);
+    defender.take_withering_hit(attacker.id, 3);
 
     assert_eq!(defender.initiative, -2);
     assert_eq!(attacker.initiative, 10);
-    assert_eq!(defender.crasher_name, Some("Attacker".into()));
+    assert_eq!(defender.crasher, Some(attacker.id));
+
+    //A withering attack on someone else is no shift
+    let mut other = Character::new(String::from("Other"), 0, 1);
+    other.id = 3;
+    let crashed = other.take_withering_hit(defender.id, 2);
+    assert!(!rules::initiative_shift(&defender, &other, crashed));
+
+    //The defender crashes back the character who crashed them
+    let crashed = attacker.take_withering_hit(defender.id, 12);
+    assert!(rules::initiative_shift(&defender, &attacker, crashed));
+    defender.do_withering_hit(12, crashed);
+    assert_eq!(defender.crasher, None);
+
+    let roll = DicePool::new(0).score(vec![7, 8, 2], Vec::new());
+    assert_eq!(rules::shift_initiative(-2, &roll), 5);
+    assert_eq!(rules::shift_initiative(8, &roll), 8);
+}
+
+#[test]
+fn initiative_break() {
+    assert!(rules::crashes(2, -1));
+    assert!(!rules::crashes(-1, -3));
+    assert!(!rules::crashes(4, 0));
+    assert_eq!(rules::initiative_break(true), rules::INITIATIVE_BREAK_BONUS);
+    assert_eq!(rules::initiative_break(false), 0);
+
+    //Hitting a character who is already crashed grants no break
+    let mut attacker = Character::new(String::from("Attacker"), 0, 1);
+    let mut defender = Character::new(String::from("Defender"), 0, 1);
+    defender.initiative = -1;
+    attacker.do_withering_hit(2, defender.take_withering_hit(attacker.id, 2));
+    assert_eq!(attacker.initiative, 3);
+}
+
+#[test]
+fn crash_duration() {
+    assert!(!rules::crash_recovers(0));
+    assert!(!rules::crash_recovers(1));
+    assert!(rules::crash_recovers(rules::CRASH_RECOVERY_TURNS - 1));
+
+    let mut char = Character::new(String::from("Test"), 0, 5);
+    char.initiative = -1;
+    char.crasher = Some(2);
+    for _ in 0..rules::CRASH_RECOVERY_TURNS - 1 {
+        char.finish();
+        assert!(char.crashed());
+    }
+    char.finish();
+    assert_eq!(char.initiative, rules::CRASH_RECOVERY_INITIATIVE);
+    assert_eq!(char.crashed_turns, 0);
+    assert_eq!(char.crasher, None);
+}
+
+fn party() -> Vec<Character> {
+    vec![
+        Character::new(String::from("Alpha"), 6, 7),
+        Character::new(String::from("Beta"), 4, 7),
+        Character::new(String::from("Gamma"), 8, 7),
+    ]
+}
+
+fn initiatives(encounter: &Encounter) -> Vec<(String, i32)> {
+    encounter.char_iter().map(|x| (x.name.clone(), x.initiative)).collect()
+}
+
+#[test]
+fn seeded_join_battle() {
+    let encounter = Encounter::from_characters(party(), 42);
+    assert_eq!(
+        initiatives(&encounter),
+        vec![("Gamma".into(), 8), ("Alpha".into(), 6), ("Beta".into(), 5)]
+    );
+    assert_eq!(initiatives(&encounter), initiatives(&Encounter::from_characters(party(), 42)));
+}
+
+#[test]
+fn saved_rng_state_replays() {
+    let mut encounter = Encounter::from_characters(party(), 7);
+    let mut restored: Encounter =
+        serde_json::from_str(&serde_json::to_string(&encounter).unwrap()).unwrap();
+    let roll = encounter.add_char(Character::new(String::from("Delta"), 10, 7));
+    assert_eq!(roll, restored.add_char(Character::new(String::from("Delta"), 10, 7)));
+    assert_eq!(initiatives(&encounter), initiatives(&restored));
+}
+
+#[test]
+fn attack_damage_dice() {
+    let attack = |damage: &str| Attack::new("Sword", 8, damage);
+    assert_eq!(attack("12L").damage_dice(), 12);
+    assert_eq!(attack(" 7B").damage_dice(), 7);
+    assert_eq!(attack("special").damage_dice(), 0);
+}
+
+#[test]
+fn defense_with_onslaught() {
+    let mut char = Character::new(String::from("Test"), 0, 1);
+    char.evasion = 3;
+    char.parry = 4;
+    assert_eq!(char.effective_defense(), 4);
+    char.onslaught = -2;
+    assert_eq!(char.effective_defense(), 2);
+    char.onslaught = -6;
+    assert_eq!(char.effective_defense(), 0);
+}
+
+#[test]
+fn effective_defense_modifiers() {
+    let mut char = Character::new(String::from("Test"), 0, 7);
+    char.evasion = 5;
+    char.parry = 3;
+    char.onslaught = -1;
+    char.health.take(DamageType::Lethal, 2);
+    assert_eq!(char.effective_evasion(), 3);
+    assert_eq!(char.effective_parry(), 1);
+
+    //A temporary bonus like full defense offsets the penalties
+    char.defense_modifier = 2;
+    assert_eq!(char.effective_evasion(), 5);
+    assert_eq!(char.effective_parry(), 3);
+    assert_eq!(char.effective_defense(), 5);
+    assert_eq!(char.evasion, 5);
+}
+
+#[test]
+fn rolled_withering_attack() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let attacker = Character::new(String::from("Attacker"), 0, 1);
+    let mut defender = Character::new(String::from("Defender"), 0, 1);
+    let weak = Attack::new("Weak", 0, "0L");
+
+    //No successes against a defense of 1 can never hit
+    defender.parry = 1;
+    let roll = attacker.roll_withering(&weak, &defender, &mut rng);
+    assert!(!roll.hit());
+    assert_eq!(roll.damage(), -1);
+
+    //Soak can not push the pool below the minimum damage
+    defender.parry = 0;
+    defender.soak = 10;
+    let roll = attacker.roll_withering(&weak, &defender, &mut rng);
+    assert!(roll.hit());
+    assert_eq!(roll.damage.unwrap().faces.len(), MIN_WITHERING_DAMAGE as usize);
+
+    //Threshold successes add to the damage pool
+    let strong = Attack::new("Strong", 20, "10L");
+    defender.soak = 2;
+    let roll = attacker.roll_withering(&strong, &defender, &mut rng);
+    let faces = roll.damage.as_ref().unwrap().faces.len() as i32;
+    assert_eq!(faces, 10 + roll.accuracy.successes - 2);
+}
+
+#[test]
+fn rolled_decisive_attack() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let mut attacker = Character::new(String::from("Attacker"), 0, 1);
+    let mut defender = Character::new(String::from("Defender"), 0, 1);
+    let attack = Attack::new("Sword", 0, "12L");
+
+    defender.evasion = 2;
+    assert!(!attacker.roll_decisive(&attack, &defender, &mut rng).hit());
+
+    //Damage dice come from initiative, not from the weapon
+    defender.evasion = 0;
+    attacker.initiative = 7;
+    let roll = attacker.roll_decisive(&attack, &defender, &mut rng);
+    assert!(roll.hit());
+    assert_eq!(roll.damage.unwrap().faces.len(), 7);
+}
+
+#[test]
+fn standard_health_track() {
+    let track = HealthTrack::new(7);
+    let penalties: Vec<Option<i32>> = (0..track.levels()).map(|x| track.penalty(x)).collect();
+    assert_eq!(
+        penalties,
+        vec![Some(0), Some(-1), Some(-1), Some(-2), Some(-2), Some(-4), None]
+    );
+    assert_eq!(HealthTrack::new(1).levels(), 1);
+    assert_eq!(HealthTrack::new(10).levels(), 10);
+}
+
+#[test]
+fn wound_penalties() {
+    let mut char = Character::new(String::from("Test"), 0, 7);
+    char.evasion = 4;
+    assert_eq!(char.wound_penalty(), 0);
+    char.health.take(DamageType::Bashing, 1);
+    assert_eq!(char.wound_penalty(), 0);
+    char.health.take(DamageType::Lethal, 2);
+    assert_eq!(char.wound_penalty(), -1);
+    assert_eq!(char.effective_defense(), 3);
+    char.health.take(DamageType::Lethal, 4);
+    assert_eq!(char.wound_penalty(), -4);
+    assert!(char.incapacitated());
+    assert!(!char.dead());
+
+    //Severe damage is marked to the left of bashing damage
+    assert_eq!(char.health.level(0), Some(DamageType::Lethal));
+    assert_eq!(char.health.level(6), Some(DamageType::Bashing));
+}
+
+#[test]
+fn health_json_compatibility() {
+    let old = r#"{"name": "Old", "crasher_name": null, "joinbattle": 3, "health": 7,
+        "current_health": 4, "evasion": 1, "parry": 2, "soak": 3, "attacks": null,
+        "specials": null}"#;
+    let char: Character = serde_json::from_str(old).unwrap();
+    assert_eq!(char.health.levels(), 7);
+    assert_eq!(char.health.damage_of(DamageType::Lethal), 3);
+
+    let mut char = Character::new(String::from("New"), 0, 7);
+    char.health.take(DamageType::Aggravated, 1);
+    char.health.take(DamageType::Bashing, 2);
+    let copy: Character = serde_json::from_str(&serde_json::to_string(&char).unwrap()).unwrap();
+    assert_eq!(copy.health, char.health);
+
+    let fresh = r#"{"name": "Fresh", "crasher_name": null, "joinbattle": 3, "health": 3,
+        "evasion": 1, "parry": 2, "soak": 3, "attacks": null, "specials": null}"#;
+    let char: Character = serde_json::from_str(fresh).unwrap();
+    assert_eq!(char.health.remaining(), 3);
+}
+
+#[test]
+fn attack_damage_type() {
+    let mut attack = Attack::new("Fist", 5, "7B");
+    assert_eq!(attack.damage_type(), DamageType::Bashing);
+    attack.damage = "7".into();
+    assert_eq!(attack.damage_type(), DamageType::Lethal);
+    attack.damage_type = Some(DamageType::Aggravated);
+    assert_eq!(attack.damage_type(), DamageType::Aggravated);
+}
+
+#[test]
+fn bashing_overflow_and_incapacitation() {
+    let mut track = HealthTrack::new(4);
+    assert_eq!(track.take(DamageType::Bashing, 4), 4);
+    assert!(track.incapacitated());
+    assert!(!track.dead());
+
+    //Bashing damage on a full track rolls over into lethal
+    assert_eq!(track.take(DamageType::Bashing, 2), 2);
+    assert_eq!(track.damage_of(DamageType::Bashing), 2);
+    assert_eq!(track.damage_of(DamageType::Lethal), 2);
+    assert_eq!(track.to_string(), "0/4 2B 2L");
+
+    //Lethal damage replaces the remaining bashing
+    assert_eq!(track.take(DamageType::Lethal, 5), 2);
+    assert_eq!(track.damage_of(DamageType::Lethal), 4);
+    assert!(track.dead());
+
+    //Nothing but aggravated damage can still change a lethal track
+    assert_eq!(track.take(DamageType::Bashing, 1), 0);
+    assert_eq!(track.take(DamageType::Aggravated, 1), 1);
+    assert_eq!(track.to_string(), "0/4 3L 1A");
+}
+
+#[test]
+fn aggravated_damage_heals_separately() {
+    let mut char = Character::new(String::from("Test"), 0, 7);
+    char.take_decisive_hit(2, DamageType::Aggravated);
+    char.take_decisive_hit(3, DamageType::Bashing);
+    char.health.heal(DamageType::Lethal, 5);
+    char.health.heal(DamageType::Bashing, 5);
+    assert_eq!(char.health.damage_of(DamageType::Aggravated), 2);
+    assert_eq!(char.health.damage(), 2);
+}
+
+#[test]
+fn undo_and_redo() {
+    let mut encounter = Encounter::from_characters(party(), 42);
+    let before = initiatives(&encounter);
+    assert!(!encounter.undo());
+
+    let alpha = id_of(&encounter, "Alpha");
+    encounter.set_stat(alpha, Stat::Initiative, -5).unwrap();
+    let after = initiatives(&encounter);
+
+    assert!(encounter.undo());
+    assert_eq!(initiatives(&encounter), before);
+    let last = encounter.log_iter().last().unwrap().to_string();
+    assert_eq!(last, "Undo: Alpha initiative set to -5.");
+    assert!(!encounter.undo());
+
+    assert!(encounter.redo());
+    assert_eq!(initiatives(&encounter), after);
+    let last = encounter.log_iter().last().unwrap().to_string();
+    assert_eq!(last, "Redo: Alpha initiative set to -5.");
+    assert!(!encounter.redo());
+
+    //A new action drops the redo history
+    encounter.undo();
+    encounter.new_round();
+    assert!(!encounter.redo());
+}
+
+#[test]
+fn undo_replays_rolls() {
+    let armed = || {
+        let mut chars = party();
+        for char in chars.iter_mut() {
+            char.attacks = Some(vec![Attack::new("Sword", 8, "10L")]);
+        }
+        Encounter::from_characters(chars, 42)
+    };
+    let mut encounter = armed();
+    let (alpha, beta, gamma) =
+        (id_of(&encounter, "Alpha"), id_of(&encounter, "Beta"), id_of(&encounter, "Gamma"));
+    let last = |x: &Encounter| x.log_iter().last().unwrap().clone();
+
+    //Taking an undone action again rolls the same dice
+    encounter.rolled_withering_attack(alpha, 0, beta).unwrap();
+    let first = last(&encounter);
+    encounter.undo();
+    encounter.rolled_withering_attack(alpha, 0, beta).unwrap();
+    assert_eq!(last(&encounter), first);
+
+    //and redoing it leaves later rolls as they would have been without the undo
+    encounter.undo();
+    encounter.redo();
+    encounter.rolled_withering_attack(gamma, 0, beta).unwrap();
+    let mut replay = armed();
+    replay.rolled_withering_attack(alpha, 0, beta).unwrap();
+    replay.rolled_withering_attack(gamma, 0, beta).unwrap();
+    assert_eq!(last(&encounter), last(&replay));
+}
+
+#[test]
+fn undo_add_and_remove() {
+    let mut encounter = Encounter::from_characters(party(), 1);
+    encounter.join_combat(Character::new(String::from("Delta"), 3, 7));
+    encounter.remove_char(id_of(&encounter, "Alpha")).unwrap();
+    assert_eq!(encounter.charcount(), 3);
+    encounter.undo();
+    assert_eq!(encounter.charcount(), 4);
+    encounter.undo();
+    assert_eq!(encounter.charcount(), 3);
+    assert_eq!(encounter.count_name("Delta"), 0);
+}
+
+#[test]
+fn bounded_history() {
+    let mut encounter = Encounter::from_characters(party(), 1);
+    for _ in 0..HISTORY_LIMIT + 10 {
+        encounter.checkpoint();
+    }
+    let mut undone = 0;
+    while encounter.undo() {
+        undone += 1;
+    }
+    assert_eq!(undone, HISTORY_LIMIT);
+}
+
+#[test]
+fn join_battle_events() {
+    let encounter = Encounter::from_characters(party(), 42);
+    let joined: Vec<String> = encounter
+        .log_iter()
+        .filter_map(|x| match x {
+            CombatEvent::CharacterJoined { character, roll } => {
+                assert!(roll.is_some());
+                assert_eq!(character.id, id_of(&encounter, &character.name));
+                Some(character.name.clone())
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(joined, vec!["Alpha", "Beta", "Gamma"]);
+}
+
+#[test]
+fn event_log_in_save_file() {
+    let mut encounter = Encounter::from_characters(party(), 42);
+    encounter.log(CombatEvent::DecisiveHit {
+        attacker: encounter.char_by_id(1).unwrap().char_ref(),
+        defender: encounter.char_by_id(2).unwrap().char_ref(),
+        damage: 3,
+        kind: DamageType::Bashing,
+        roll: None,
+    });
+    let saved = serde_json::to_string(&encounter).unwrap();
+    let restored: Encounter = serde_json::from_str(&saved).unwrap();
+    let last = restored.log_iter().last().unwrap();
+    assert_eq!(last, encounter.log_iter().last().unwrap());
+    assert_eq!(last.to_string(), "Alpha hits decisive attack on Beta, with 3B damage!");
+
+    //Older saves stored the log as plain text and named the crasher
+    let mut value: serde_json::Value = serde_json::from_str(&saved).unwrap();
+    value["log"] = serde_json::json!(["Alpha joined combat! "]);
+    for char in value["characters"].as_array_mut().unwrap() {
+        if char["name"] == "Beta" {
+            char["crasher_name"] = serde_json::json!("Alpha");
+        }
+    }
+    let restored = Encounter::parse(&value.to_string()).unwrap();
+    let lines: Vec<String> = restored.log_iter().map(|x| x.to_string()).collect();
+    assert_eq!(lines, ["Alpha joined combat! "]);
+    let alpha = id_of(&restored, "Alpha");
+    let beta = id_of(&restored, "Beta");
+    assert_eq!(restored.char_by_id(beta).unwrap().crasher, Some(alpha));
+}
+
+fn id_of(encounter: &Encounter, name: &str) -> CharId {
+    encounter.char_iter().find(|x| x.name == name).unwrap().id
+}
+
+#[test]
+fn engine_withering_attack() {
+    let mut encounter = Encounter::from_characters(party(), 42);
+    let alpha = id_of(&encounter, "Alpha");
+    let beta = id_of(&encounter, "Beta");
+    encounter.set_stat(beta, Stat::Initiative, 2).unwrap();
+
+    let outcome = encounter.withering_attack(alpha, beta, Some(4)).unwrap();
+    assert!(outcome.hit);
+    assert!(outcome.crashed);
+    assert_eq!(encounter.char_by_id(beta).unwrap().initiative, -2);
+    assert_eq!(encounter.char_by_id(beta).unwrap().crasher, Some(alpha));
+    assert_eq!(encounter.char_by_id(alpha).unwrap().initiative, 6 + 5 + 5);
+
+    let events: Vec<String> = encounter.log_iter().skip(4).map(|x| x.to_string()).collect();
+    assert_eq!(
+        events,
+        vec![
+            "Alpha hits a withering attack on Beta for 4 damage.",
+            "Alpha crashed Beta!",
+            "Alpha gains 5 initiative from Initiative Break.",
+        ]
+    );
+
+    //Attacks are undone as a whole
+    encounter.undo();
+    assert_eq!(encounter.char_by_id(beta).unwrap().initiative, 2);
+}
+
+#[test]
+fn engine_initiative_shift() {
+    let mut encounter = Encounter::from_characters(party(), 42);
+    let alpha = id_of(&encounter, "Alpha");
+    let beta = id_of(&encounter, "Beta");
+    let gamma = id_of(&encounter, "Gamma");
+    encounter.set_stat(alpha, Stat::Initiative, 1).unwrap();
+    let outcome = encounter.withering_attack(beta, alpha, Some(3)).unwrap();
+    assert!(outcome.crashed && !outcome.shifted);
+
+    //Alpha crashes Beta right back and goes before everyone else
+    encounter.set_stat(beta, Stat::Initiative, 1).unwrap();
+    let outcome = encounter.withering_attack(alpha, beta, Some(3)).unwrap();
+    assert!(outcome.crashed && outcome.shifted);
+    let char = encounter.char_by_id(alpha).unwrap();
+    assert!(!char.done);
+    assert!(char.initiative >= 3);
+    assert!(char.initiative < encounter.char_by_id(gamma).unwrap().initiative);
+    assert_eq!(encounter.position_of(alpha), Some(0));
+    let last = encounter.log_iter().last().unwrap().to_string();
+    assert!(last.starts_with(&format!("Alpha shifts to {} initiative", char.initiative)));
+
+    //The extra turn ends like any other
+    encounter.toggle_done(alpha).unwrap();
+    assert_ne!(encounter.position_of(alpha), Some(0));
+}
+
+#[test]
+fn engine_rejects_illegal_actions() {
+    let mut encounter = Encounter::from_characters(party(), 42);
+    let alpha = id_of(&encounter, "Alpha");
+    let beta = id_of(&encounter, "Beta");
+    assert_eq!(encounter.withering_attack(alpha, alpha, Some(1)), Err(CombatError::SameCharacter));
+    assert_eq!(encounter.withering_attack(alpha, 9, Some(1)), Err(CombatError::NoSuchCharacter));
+    assert_eq!(encounter.rolled_withering_attack(alpha, 0, beta), Err(CombatError::NoSuchAttack));
+
+    encounter.set_stat(alpha, Stat::Initiative, -1).unwrap();
+    assert_eq!(
+        encounter.decisive_attack(alpha, beta, Some(3), DamageType::Lethal),
+        Err(CombatError::Crashed("Alpha".into()))
+    );
+
+    encounter.change_health(beta, 7, DamageType::Lethal).unwrap();
+    assert_eq!(
+        encounter.withering_attack(beta, alpha, Some(3)),
+        Err(CombatError::Incapacitated("Beta".into()))
+    );
+
+    let mut encounter = Encounter::from_characters(vec![Character::new("Solo".into(), 1, 7)], 1);
+    let solo = id_of(&encounter, "Solo");
+    assert_eq!(encounter.remove_char(solo), Err(CombatError::LastCharacter));
+}
+
+#[test]
+fn engine_decisive_attack() {
+    let mut encounter = Encounter::from_characters(party(), 42);
+    let gamma = id_of(&encounter, "Gamma");
+    let beta = id_of(&encounter, "Beta");
+    let outcome = encounter.decisive_attack(gamma, beta, Some(3), DamageType::Bashing).unwrap();
+    assert_eq!(outcome.damage, 3);
+    let beta = encounter.char_by_id(beta).unwrap();
+    assert_eq!(beta.health.damage_of(DamageType::Bashing), 3);
+    let gamma = encounter.char_by_id(gamma).unwrap();
+    assert_eq!(gamma.initiative, 3);
+    assert!(gamma.done);
+}
+
+#[test]
+fn stable_character_ids() {
+    let mut encounter = Encounter::from_characters(party(), 42);
+    let ids: Vec<CharId> = party().iter().map(|x| id_of(&encounter, &x.name)).collect();
+    assert_eq!(ids, vec![1, 2, 3]);
+
+    //Ids follow characters through re-sorting and removal
+    let (alpha, beta, gamma) = (ids[0], ids[1], ids[2]);
+    encounter.set_stat(beta, Stat::Initiative, 20).unwrap();
+    assert_eq!(encounter.position_of(beta), Some(0));
+    encounter.remove_char(gamma).unwrap();
+    assert_eq!(encounter.char_by_id(alpha).unwrap().name, "Alpha");
+    assert!(encounter.char_by_id(gamma).is_none());
+    assert_eq!(encounter.toggle_done(gamma), Err(CombatError::NoSuchCharacter));
+
+    //Removed ids are never handed out again
+    let delta = encounter.join_combat(Character::new(String::from("Delta"), 3, 7));
+    assert_eq!(delta, 4);
+    assert_eq!(encounter.char_by_id(delta).unwrap().name, "Delta");
+}
+
+#[test]
+fn ids_for_older_saves() {
+    let encounter = Encounter::from_characters(party(), 42);
+    let mut value = serde_json::to_value(&encounter).unwrap();
+    value.as_object_mut().unwrap().remove("next_id");
+    for char in value["characters"].as_array_mut().unwrap() {
+        char.as_object_mut().unwrap().remove("id");
+    }
+    let mut restored = Encounter::parse(&value.to_string()).unwrap();
+    let mut ids: Vec<CharId> = restored.char_iter().map(|x| x.id).collect();
+    ids.sort();
+    assert_eq!(ids, vec![1, 2, 3]);
+    assert_eq!(restored.join_combat(Character::new(String::from("Delta"), 3, 7)), 4);
+}
+
+#[test]
+fn turn_order() {
+    let mut encounter = Encounter::from_characters(party(), 42);
+    assert_eq!(encounter.current(), None);
+    let (alpha, beta, gamma) = (1, 2, 3);
+    assert_eq!(encounter.next_turn(), Some(gamma));
+    assert_eq!(encounter.next_turn(), Some(alpha));
+    assert!(encounter.char_by_id(gamma).unwrap().done);
+
+    //Attacking ends the turn and hands it on
+    encounter.withering_attack(alpha, gamma, Some(0)).unwrap();
+    assert_eq!(encounter.current(), Some(beta));
+    assert_eq!(encounter.log_iter().last().unwrap().to_string(), "It is Beta's turn.");
+
+    //Once everyone is done a new round starts
+    assert_eq!(encounter.round(), 1);
+    assert_eq!(encounter.next_turn(), Some(gamma));
+    assert_eq!(encounter.round(), 2);
+
+    encounter.undo();
+    assert_eq!(encounter.current(), Some(beta));
+    assert_eq!(encounter.round(), 1);
+
+    //Characters who can no longer act are skipped
+    encounter.change_health(beta, 7, DamageType::Lethal).unwrap();
+    assert_eq!(encounter.current(), Some(gamma));
+    assert_eq!(encounter.round(), 2);
+    encounter.remove_char(gamma).unwrap();
+    assert_eq!(encounter.current(), Some(alpha));
+}
+
+#[test]
+fn turn_order_ties() {
+    let mut encounter = Encounter::from_characters(party(), 42);
+    for id in 1..=3 {
+        encounter.set_stat(id, Stat::Initiative, 10).unwrap();
+    }
+    let mut alpha = encounter.char_by_id(1).unwrap().clone();
+    let mut beta = encounter.char_by_id(2).unwrap().clone();
+    alpha.jb_successes = 2;
+    beta.jb_successes = 2;
+    assert!(alpha.sortkey() < beta.sortkey());
+    beta.jb_successes = 3;
+    assert!(beta.sortkey() < alpha.sortkey());
+
+    //Equal in every way, so a coin flip decides
+    let mut twins = Vec::new();
+    for seed in 0..8 {
+        let mut encounter = Encounter::from_characters(
+            vec![Character::new("A".into(), 0, 7), Character::new("B".into(), 0, 7)],
+            seed,
+        );
+        twins.push(encounter.next_turn().unwrap());
+    }
+    assert!(twins.contains(&1) && twins.contains(&2));
+}
+
+#[test]
+fn delayed_actions() {
+    let mut encounter = Encounter::from_characters(party(), 42);
+    let (alpha, beta, gamma) = (1, 2, 3);
+    assert_eq!(encounter.next_turn(), Some(gamma));
+
+    //Gamma holds their action and Alpha goes instead
+    encounter.delay(gamma).unwrap();
+    assert_eq!(encounter.current(), Some(alpha));
+    assert!(encounter.char_by_id(gamma).unwrap().delayed);
+    assert_eq!(encounter.delay(gamma), Err(CombatError::CannotDelay("Gamma".into())));
+    assert_eq!(encounter.resume(beta), Err(CombatError::NotDelayed("Beta".into())));
+
+    //Gamma steps back in before Beta
+    assert_eq!(encounter.next_turn(), Some(beta));
+    encounter.resume(gamma).unwrap();
+    assert_eq!(encounter.current(), Some(gamma));
+    assert_eq!(encounter.next_turn(), Some(beta));
+
+    //A delay that is never taken comes at the end of the round
+    encounter.new_round();
+    encounter.delay(gamma).unwrap();
+    assert_eq!(encounter.next_turn(), Some(beta));
+    assert_eq!(encounter.next_turn(), Some(gamma));
+    assert!(!encounter.char_by_id(gamma).unwrap().delayed);
+    assert_eq!(encounter.round(), 2);
+
+    //Delays are kept in the save file
+    encounter.next_turn();
+    encounter.delay(encounter.current().unwrap()).unwrap();
+    let saved = serde_json::to_string(&encounter).unwrap();
+    let restored: Encounter = serde_json::from_str(&saved).unwrap();
+    assert!(restored.char_iter().any(|x| x.delayed));
+    assert_eq!(restored.current(), encounter.current());
+}
+
+#[test]
+fn sides() {
+    let text = r#"[{"name": "Hero", "joinbattle": 3, "health": 7, "evasion": 2, "parry": 3,
+        "soak": 2, "attacks": null, "specials": null},
+        {"name": "Stranger", "side": "Neutral", "joinbattle": 3, "health": 7, "evasion": 2,
+        "parry": 3, "soak": 2, "attacks": null, "specials": null}]"#;
+    let chars = Character::parse_with_side(text, Side::Players).unwrap();
+    assert_eq!(chars[0].side, Side::Players);
+    assert_eq!(chars[1].side, Side::Neutral);
+
+    assert!(Side::Players.hostile_to(&Side::Enemies));
+    assert!(Side::Custom("Cult".into()).hostile_to(&Side::Players));
+    assert!(!Side::Neutral.hostile_to(&Side::Enemies));
+    assert!(!Side::Enemies.hostile_to(&Side::Enemies));
+
+    let mut encounter = Encounter::from_characters(party(), 42);
+    let (alpha, beta) = (1, 2);
+    encounter.set_side(alpha, Side::Players).unwrap();
+    encounter.set_side(beta, Side::Enemies).unwrap();
+    assert_eq!(encounter.sides(), vec![Side::Neutral, Side::Players, Side::Enemies]);
+    let enemies: Vec<CharId> = encounter.living_enemies(&Side::Players).map(|x| x.id).collect();
+    assert_eq!(enemies, vec![beta]);
+
+    encounter.change_health(beta, 7, DamageType::Lethal).unwrap();
+    assert_eq!(encounter.living(&Side::Enemies).count(), 0);
+    assert_eq!(encounter.living(&Side::Players).count(), 1);
+
+    encounter.undo();
+    encounter.undo();
+    assert_eq!(encounter.char_by_id(beta).unwrap().side, Side::Neutral);
+}
+
+#[test]
+fn victory_and_summary() {
+    let mut encounter = Encounter::from_characters(party(), 42);
+    let (alpha, beta, gamma) = (1, 2, 3);
+    assert!(!encounter.is_over());
+    encounter.set_side(alpha, Side::Players).unwrap();
+    encounter.set_side(gamma, Side::Players).unwrap();
+    encounter.set_side(beta, Side::Enemies).unwrap();
+    assert!(!encounter.is_over());
+
+    encounter.withering_attack(alpha, beta, Some(7)).unwrap();
+    encounter.decisive_attack(gamma, beta, Some(4), DamageType::Lethal).unwrap();
+    //A hit that gets undone does not count
+    encounter.decisive_attack(alpha, beta, Some(2), DamageType::Lethal).unwrap();
+    encounter.undo();
+    encounter.decisive_attack(alpha, beta, Some(3), DamageType::Lethal).unwrap();
+    assert!(encounter.is_over());
+    assert_eq!(encounter.winner(), Some(Side::Players));
+    assert_eq!(
+        encounter.log_iter().last().unwrap().to_string(),
+        "Combat is over, Players stand victorious!"
+    );
+
+    let summary = encounter.summary();
+    assert_eq!(summary.winner, Some(Side::Players));
+    let alpha = summary.character(alpha).unwrap();
+    assert_eq!((alpha.withering_dealt, alpha.damage_dealt), (7, 3));
+    assert_eq!((alpha.crashes, alpha.kills), (1, 1));
+    let beta = summary.character(beta).unwrap();
+    assert_eq!((beta.withering_taken, beta.damage_taken), (7, 7));
+    assert!(!beta.standing);
+    assert_eq!(summary.character(gamma).unwrap().kills, 0);
+    assert_eq!(summary.lines().len(), 3 + 3);
+
+    //Undoing the last hit reopens the combat
+    encounter.undo();
+    assert!(!encounter.is_over());
+    assert_eq!(encounter.summary().character(2).unwrap().damage_taken, 4);
+    encounter.redo();
+    assert!(encounter.is_over());
+    assert_eq!(encounter.summary().character(2).unwrap().damage_taken, 7);
+
+    //A battle group routed by withering damage is a kill too
+    let mut group = soldiers();
+    group.battle_group.as_mut().unwrap().willpower = 0;
+    let mut hero = Character::new(String::from("Hero"), 5, 7);
+    hero.side = Side::Players;
+    let mut encounter = Encounter::from_characters(vec![hero, group], 3);
+    let (hero, group) = (id_of(&encounter, "Hero"), id_of(&encounter, "Soldiers"));
+    encounter.withering_attack(hero, group, Some(12)).unwrap();
+    assert!(encounter.is_over());
+    assert_eq!(encounter.summary().character(hero).unwrap().kills, 1);
+}
+
+#[test]
+fn motes_and_willpower() {
+    let text = r#"[{"name": "Solar", "joinbattle": 5, "health": 7, "evasion": 3, "parry": 4,
+        "soak": 3, "attacks": null, "specials": null, "essence": 2, "personal": 13,
+        "peripheral": 33, "willpower": {"current": 4, "max": 6}}]"#;
+    let solar = Character::parse_with_side(text, Side::Players).unwrap().remove(0);
+    assert_eq!(solar.essence, 2);
+    assert_eq!(solar.personal, Pool::new(13));
+    assert_eq!(solar.willpower.to_string(), "4/6");
+
+    let foe = Character::new(String::from("Foe"), 2, 7);
+    let mut encounter = Encounter::from_characters(vec![solar, foe], 7);
+    let id = id_of(&encounter, "Solar");
+    encounter.spend(id, Resource::Peripheral, 10).unwrap();
+    encounter.spend(id, Resource::Personal, 2).unwrap();
+    encounter.spend(id, Resource::Willpower, -5).unwrap();
+    assert_eq!(
+        encounter.spend(id, Resource::Personal, 12),
+        Err(CombatError::NotEnough("Solar".into(), Resource::Personal))
+    );
+    assert_eq!(encounter.log_iter().last().unwrap().to_string(), "Solar regains 2 willpower.");
+    assert_eq!(encounter.spend(id, Resource::Willpower, 0), Err(CombatError::NoAmount));
+    assert_eq!(encounter.spend(id, Resource::Willpower, -1), Err(CombatError::NoAmount));
+    let char = encounter.char_by_id(id).unwrap();
+    assert_eq!((char.peripheral.current, char.personal.current), (23, 11));
+    assert_eq!(char.willpower.current, 6);
+
+    //Respiration fills peripheral motes before personal ones
+    encounter.new_round();
+    encounter.new_round();
+    let char = encounter.char_by_id(id).unwrap();
+    assert_eq!((char.peripheral.current, char.personal.current), (33, 11));
+    encounter.new_round();
+    assert_eq!(encounter.char_by_id(id).unwrap().personal.current, 13);
+
+    let saved = serde_json::to_string(&encounter).unwrap();
+    let restored: Encounter = serde_json::from_str(&saved).unwrap();
+    assert_eq!(restored.char_by_id(id).unwrap().willpower, Pool::new(6));
+}
+
+#[test]
+fn anima_banner() {
+    assert_eq!(Anima::Dim.rise(2), Anima::Burning);
+    assert_eq!(Anima::Burning.rise(5), Anima::Bonfire);
+    assert_eq!(Anima::Glowing.fall().fall(), Anima::Dim);
+    assert_eq!(rules::anima_levels(4), 0);
+    assert_eq!(rules::anima_levels(12), 2);
+
+    let mut solar = Character::new(String::from("Solar"), 5, 7);
+    solar.peripheral = Pool::new(30);
+    solar.personal = Pool::new(10);
+    let mut encounter = Encounter::from_characters(vec![solar], 7);
+    let id = id_of(&encounter, "Solar");
+
+    //Personal motes keep the banner dim
+    encounter.spend(id, Resource::Personal, 8).unwrap();
+    assert_eq!(encounter.char_by_id(id).unwrap().anima, Anima::Dim);
+    //Only spending five peripheral motes at once makes it flare
+    encounter.spend(id, Resource::Peripheral, 3).unwrap();
+    encounter.spend(id, Resource::Peripheral, 3).unwrap();
+    assert_eq!(encounter.char_by_id(id).unwrap().anima, Anima::Dim);
+    encounter.spend(id, Resource::Peripheral, 6).unwrap();
+    assert_eq!(encounter.char_by_id(id).unwrap().anima, Anima::Glowing);
+    assert_eq!(encounter.log_iter().last().unwrap().to_string(), "Solar's anima is now Glowing.");
+    encounter.spend(id, Resource::Peripheral, 5).unwrap();
+    assert_eq!(encounter.char_by_id(id).unwrap().anima, Anima::Burning);
+
+    //It only fades over rounds without peripheral spending
+    encounter.new_round();
+    assert_eq!(encounter.char_by_id(id).unwrap().anima, Anima::Burning);
+    encounter.new_round();
+    assert_eq!(encounter.char_by_id(id).unwrap().anima, Anima::Glowing);
+    encounter.undo();
+    assert_eq!(encounter.char_by_id(id).unwrap().anima, Anima::Burning);
+}
+
+#[test]
+fn engine_save_and_load() {
+    let path = std::env::temp_dir().join("exalted_combat_engine_test.json");
+    let path = path.to_str().unwrap();
+    let mut encounter = Encounter::from_characters(party(), 42);
+    encounter.new_round();
+    encounter.save(path).unwrap();
+    let loaded = Encounter::load(path).unwrap();
+    assert_eq!(initiatives(&loaded), initiatives(&encounter));
+    assert_eq!(loaded.log_len(), encounter.log_len());
+
+    //A truncated save is an error that must not be mistaken for a missing one
+    let saved = std::fs::read_to_string(path).unwrap();
+    std::fs::write(path, &saved[..saved.len() / 2]).unwrap();
+    let error = Encounter::load(path).unwrap_err();
+    assert_ne!(error.kind(), std::io::ErrorKind::NotFound);
+    std::fs::remove_file(path).unwrap();
+    let error = Encounter::load(path).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+}
+
+fn solar() -> Character {
+    let text = r#"[{"name": "Solar", "joinbattle": 5, "health": 7, "evasion": 3, "parry": 4,
+        "soak": 3, "attacks": null, "specials": null, "essence": 2, "personal": 6,
+        "peripheral": 4, "willpower": 3, "charms": [
+            {"name": "Excellent Strike", "cost": "3m, 1wp", "type": "Supplemental"},
+            {"name": "Iron Skin", "cost": {"motes": 2}, "type": "Reflexive",
+                "duration": {"Rounds": 2}},
+            {"name": "Hungry Tiger", "cost": "1i", "type": "Simple", "duration": {"Turns": 1}},
+            {"name": "Ox-Body", "type": "Permanent"}]}]"#;
+    Character::parse_with_side(text, Side::Players).unwrap().remove(0)
+}
+
+#[test]
+fn charm_costs() {
+    let cost = Cost::parse("5m, 1wp 2i").unwrap();
+    assert_eq!(cost, Cost { motes: 5, willpower: 1, initiative: 2 });
+    assert_eq!(cost.to_string(), "5m, 1wp, 2i");
+    assert_eq!(Cost::default().to_string(), "-");
+    assert_eq!(Cost::parse("5x"), None);
+
+    let charms = solar().charms.unwrap();
+    assert_eq!(charms[0].cost, Cost { motes: 3, willpower: 1, initiative: 0 });
+    assert_eq!(charms[1].duration, Duration::Rounds(2));
+    assert_eq!(charms[3].cost, Cost::default());
+}
+
+#[test]
+fn activate_charms() {
+    let foe = Character::new(String::from("Foe"), 2, 7);
+    let mut encounter = Encounter::from_characters(vec![solar(), foe], 7);
+    let id = id_of(&encounter, "Solar");
+    let charms = encounter.char_by_id(id).unwrap().charms.clone().unwrap();
+
+    assert_eq!(
+        encounter.activate_charm(id, &charms[3]),
+        Err(CombatError::Permanent("Ox-Body".into()))
+    );
+
+    //Motes come from the peripheral pool first and raise the anima
+    encounter.activate_charm(id, &charms[0]).unwrap();
+    encounter.activate_charm(id, &charms[1]).unwrap();
+    let char = encounter.char_by_id(id).unwrap();
+    assert_eq!((char.peripheral.current, char.personal.current), (0, 5));
+    assert_eq!(char.willpower.current, 2);
+    assert_eq!(char.anima, Anima::Dim);
+    assert_eq!(char.effects.len(), 1);
+    assert_eq!(char.effects[0].to_string(), "Iron Skin (2 rounds)");
+    assert!(encounter
+        .log_iter()
+        .any(|x| x.to_string() == "Solar activates Excellent Strike (3m, 1wp)."));
+    encounter.activate_charm(id, &charms[0]).unwrap();
+    assert_eq!(encounter.char_by_id(id).unwrap().personal.current, 2);
+    assert_eq!(
+        encounter.activate_charm(id, &charms[0]),
+        Err(CombatError::NotEnoughMotes("Solar".into()))
+    );
+
+    //Round effects count down with new rounds, turn effects when the character finishes a turn
+    encounter.new_round();
+    assert_eq!(encounter.char_by_id(id).unwrap().effects[0].duration, Duration::Rounds(1));
+    encounter.new_round();
+    assert!(encounter.char_by_id(id).unwrap().effects.is_empty());
+
+    encounter.set_stat(id, Stat::Initiative, 0).unwrap();
+    assert_eq!(
+        encounter.activate_charm(id, &charms[2]),
+        Err(CombatError::NoInitiative("Solar".into()))
+    );
+    encounter.set_stat(id, Stat::Initiative, 4).unwrap();
+    encounter.activate_charm(id, &charms[2]).unwrap();
+    assert_eq!(encounter.char_by_id(id).unwrap().initiative, 3);
+    while encounter.current() != Some(id) {
+        encounter.next_turn();
+    }
+    assert_eq!(encounter.char_by_id(id).unwrap().effects.len(), 1);
+    encounter.next_turn();
+    assert!(encounter.char_by_id(id).unwrap().effects.is_empty());
+
+    //Undo restores the spent motes
+    let mut encounter = Encounter::from_characters(vec![solar()], 7);
+    let id = id_of(&encounter, "Solar");
+    encounter.activate_charm(id, &charms[1]).unwrap();
+    encounter.undo();
+    assert_eq!(encounter.char_by_id(id).unwrap().peripheral.current, 4);
+
+    //Effects can be dismissed before they run out
+    encounter.activate_charm(id, &charms[1]).unwrap();
+    encounter.end_effect(id, "Iron Skin").unwrap();
+    assert!(encounter.char_by_id(id).unwrap().effects.is_empty());
+    assert_eq!(encounter.log_iter().last().unwrap().to_string(), "Solar's Iron Skin ends.");
+    assert_eq!(
+        encounter.end_effect(id, "Iron Skin"),
+        Err(CombatError::NoEffect("Iron Skin".into()))
+    );
+}
+
+#[test]
+fn durations() {
+    assert_eq!(Duration::parse("2t"), Some(Duration::Turns(2)));
+    assert_eq!(Duration::parse(" 3R "), Some(Duration::Rounds(3)));
+    assert_eq!(Duration::parse("scene"), Some(Duration::Scene));
+    assert_eq!(Duration::parse(""), Some(Duration::Indefinite));
+    assert_eq!(Duration::parse("0t"), None);
+    assert_eq!(Duration::parse("3x"), None);
+
+    let mut duration = Duration::Turns(2);
+    assert!(!duration.tick_turn());
+    assert!(!duration.tick_round());
+    assert!(duration.tick_turn());
+    assert!(!Duration::Scene.tick_round());
+}
+
+#[test]
+fn condition_modifiers() {
+    let mut char = Character::new(String::from("Hero"), 3, 7);
+    char.evasion = 3;
+    char.parry = 4;
+    char.conditions.push(Condition::prone());
+    char.conditions.push(Condition::new("Bleeding", 0, -1).with_duration(Duration::Turns(1)));
+    assert_eq!((char.effective_evasion(), char.effective_parry()), (2, 3));
+    assert_eq!(char.pool_penalty(), -4);
+    assert_eq!(char.conditions[1].to_string(), "Bleeding (1 turn, -1 dice)");
+    assert_eq!(char.conditions.iter().map(|x| x.icon()).collect::<String>(), "PB");
+
+    //Presets and gambit conditions can be told apart in the participant list
+    let mut icons: Vec<char> = Condition::presets().iter().map(|x| x.icon()).collect();
+    icons.extend(Gambit::all().iter().filter_map(|x| x.condition()).map(|x| x.icon()));
+    icons.sort();
+    icons.dedup();
+    assert_eq!(icons.len(), 7);
+
+    char.finish();
+    assert_eq!(char.conditions, vec![Condition::prone()]);
+    assert_eq!(char.expired[0].name, "Bleeding");
+    char.reset(&mut StdRng::seed_from_u64(1));
+    assert!(char.conditions.is_empty());
+
+    let text = r#"{"name": "Dazed", "pools": -2}"#;
+    let dazed: Condition = serde_json::from_str(text).unwrap();
+    assert_eq!(dazed.duration, Duration::Indefinite);
+    assert_eq!(dazed.defense, 0);
+}
+
+#[test]
+fn conditions_in_encounter() {
+    let hero = Character::new(String::from("Hero"), 3, 7);
+    let foe = Character::new(String::from("Foe"), 2, 7);
+    let mut encounter = Encounter::from_characters(vec![hero, foe], 5);
+    let hero = id_of(&encounter, "Hero");
+    let foe = id_of(&encounter, "Foe");
+
+    let blinded = Condition::blinded().with_duration(Duration::Rounds(1));
+    encounter.add_condition(hero, blinded).unwrap();
+    encounter.add_condition(foe, Condition::poisoned().with_duration(Duration::Scene)).unwrap();
+    encounter.add_condition(foe, Condition::prone()).unwrap();
+    let last = encounter.log_iter().last().unwrap().to_string();
+    assert_eq!(last, "Foe is Prone (indefinite).");
+
+    encounter.remove_condition(foe, "Prone").unwrap();
+    assert_eq!(encounter.log_iter().last().unwrap().to_string(), "Foe is no longer Prone.");
+    assert_eq!(
+        encounter.remove_condition(foe, "Prone"),
+        Err(CombatError::NoCondition("Prone".into()))
+    );
+
+    //Turn conditions run out as the character finishes a turn
+    let bleeding = Condition::new("Bleeding", 0, -1).with_duration(Duration::Turns(1));
+    encounter.add_condition(foe, bleeding).unwrap();
+    encounter.withering_attack(foe, hero, None).unwrap();
+    let last = encounter.log_iter().last().unwrap().to_string();
+    assert_eq!(last, "Foe's Bleeding wears off.");
+
+    //Round conditions run out with the round, scene ones last until the scene is reset
+    encounter.new_round();
+    assert!(encounter.char_by_id(hero).unwrap().conditions.is_empty());
+    assert!(encounter.log_iter().any(|x| x.to_string() == "Hero's Blinded wears off."));
+    assert_eq!(encounter.char_by_id(foe).unwrap().conditions.len(), 1);
+    encounter.undo();
+    assert_eq!(encounter.char_by_id(hero).unwrap().conditions.len(), 1);
+    assert_eq!(encounter.char_by_id(foe).unwrap().conditions[0].duration, Duration::Scene);
+}
+
+//Hero against two foes, for the grapple, gambit and range tests
+fn skirmish() -> Encounter {
+    let mut hero = Character::new(String::from("Hero"), 3, 7);
+    hero.side = Side::Players;
+    hero.attacks = Some(vec![
+        Attack::new("Sword", 12, "12L"),
+        Attack {
+            range: Some(Range::Long),
+            ..Attack::new("Bow", 8, "10L")
+        },
+    ]);
+    let mut foe = Character::new(String::from("Foe"), 2, 7);
+    foe.side = Side::Enemies;
+    foe.evasion = 3;
+    foe.parry = 2;
+    let mut other = Character::new(String::from("Other"), 2, 7);
+    other.side = Side::Enemies;
+    let mut encounter = Encounter::from_characters(vec![hero, foe, other], 11);
+    encounter.set_stat(id_of(&encounter, "Hero"), Stat::Initiative, 10).unwrap();
+    encounter
+}
+
+#[test]
+fn control_rounds() {
+    let mut grapple = Grapple::new(1, 2, 2);
+    assert_eq!(grapple.partner(1), 2);
+    assert!(grapple.involves(2) && !grapple.involves(3));
+    assert!(!grapple.spend());
+    assert!(!grapple.tick_round());
+    assert_eq!(grapple.control, 1);
+    assert!(grapple.tick_round());
+}
+
+#[test]
+fn grapple_and_savage() {
+    let mut encounter = skirmish();
+    let hero = id_of(&encounter, "Hero");
+    let foe = id_of(&encounter, "Foe");
+    let other = id_of(&encounter, "Other");
+    assert_eq!(
+        encounter.grapple_action(hero, GrappleAction::Throw),
+        Err(CombatError::NotGrappling("Hero".into()))
+    );
+    //A failed grapple is a failed gambit
+    assert_eq!(encounter.grapple(hero, foe, Some(1), 3), Ok(false));
+    assert_eq!(encounter.log_iter().last().unwrap().to_string(), "Hero fails to grapple Foe.");
+    assert_eq!(encounter.char_by_id(hero).unwrap().initiative, 8);
+    assert!(encounter.grapples().is_empty());
+
+    assert_eq!(encounter.grapple(hero, foe, Some(2), 3), Ok(true));
+    assert_eq!(encounter.char_by_id(hero).unwrap().initiative, 3);
+    assert_eq!(encounter.char_by_id(foe).unwrap().effective_evasion(), 1);
+    assert_eq!(encounter.grapple_of(foe).unwrap().grappler, hero);
+    let events: Vec<String> = encounter.log_iter().map(|x| x.to_string()).collect();
+    let held = ["Hero grapples Foe!".to_string(), "Hero holds Foe for 3 rounds.".to_string()];
+    assert!(events.ends_with(&held));
+
+    //Both are tied up with each other
+    assert_eq!(
+        encounter.withering_attack(hero, other, Some(1)),
+        Err(CombatError::Grappling("Hero".into()))
+    );
+    encounter.set_stat(other, Stat::Initiative, 5).unwrap();
+    assert_eq!(
+        encounter.grapple(other, foe, Some(2), 1),
+        Err(CombatError::Grappling("Foe".into()))
+    );
+    encounter.withering_attack(foe, hero, None).unwrap();
+
+    //Winning the grapple was the grappler's action this round
+    assert_eq!(encounter.savage(hero, 4, None), Err(CombatError::ControlSpent("Hero".into())));
+    encounter.new_round();
+    assert_eq!(encounter.grapple_of(hero).unwrap().control, 3);
+    let outcome = encounter.savage(hero, 4, None).unwrap();
+    assert!(outcome.hit);
+    assert_eq!(encounter.grapple_of(hero).unwrap().control, 2);
+    assert_eq!(
+        encounter.grapple_action(hero, GrappleAction::Restrain),
+        Err(CombatError::ControlSpent("Hero".into()))
+    );
+    let events: Vec<String> = encounter.log_iter().map(|x| x.to_string()).collect();
+    assert!(events.contains(&String::from("Hero savages Foe.")));
+
+    //Undo puts the control round back
+    encounter.undo();
+    assert_eq!(encounter.grapple_of(hero).unwrap().control, 3);
+    assert!(!encounter.grapple_of(hero).unwrap().spent);
+}
+
+#[test]
+fn single_round_grapple() {
+    let mut encounter = skirmish();
+    let hero = id_of(&encounter, "Hero");
+    let foe = id_of(&encounter, "Foe");
+    encounter.grapple(hero, foe, Some(2), 1).unwrap();
+    encounter.new_round();
+    assert_eq!(encounter.grapple_of(hero).unwrap().control, 1);
+    encounter.grapple_action(hero, GrappleAction::Restrain).unwrap();
+    assert!(encounter.grapples().is_empty());
+
+    //Savaging as the last to act ends the round and the grapple together
+    encounter.new_round();
+    encounter.grapple(hero, foe, Some(2), 1).unwrap();
+    encounter.new_round();
+    encounter.toggle_done(foe).unwrap();
+    encounter.toggle_done(id_of(&encounter, "Other")).unwrap();
+    assert_eq!(encounter.next_turn(), Some(hero));
+    let round = encounter.round();
+    encounter.savage(hero, 2, None).unwrap();
+    assert_eq!(encounter.round(), round + 1);
+    assert!(encounter.grapples().is_empty());
+
+    //Left unused, the round of control runs out when the next round starts
+    encounter.set_stat(hero, Stat::Initiative, 10).unwrap();
+    encounter.new_round();
+    encounter.grapple(hero, foe, Some(2), 1).unwrap();
+    encounter.new_round();
+    assert!(encounter.grapple_of(hero).is_some());
+    encounter.new_round();
+    assert!(encounter.grapples().is_empty());
+}
+
+#[test]
+fn throw_restrain_and_release() {
+    let mut encounter = skirmish();
+    let hero = id_of(&encounter, "Hero");
+    let foe = id_of(&encounter, "Foe");
+    encounter.grapple(hero, foe, Some(2), 2).unwrap();
+    encounter.new_round();
+
+    encounter.grapple_action(hero, GrappleAction::Restrain).unwrap();
+    assert!(encounter.char_by_id(foe).unwrap().done);
+    assert_eq!(encounter.grapple_of(hero).unwrap().control, 1);
+
+    //The last round of control runs out at the start of the next round
+    encounter.new_round();
+    assert!(encounter.grapple_of(hero).is_some());
+    encounter.new_round();
+    assert!(encounter.grapple_of(hero).is_none());
+    assert!(encounter.char_by_id(foe).unwrap().conditions.is_empty());
+    assert_eq!(encounter.log_iter().last().unwrap().to_string(), "Hero no longer holds Foe.");
+
+    encounter.set_stat(hero, Stat::Initiative, 10).unwrap();
+    encounter.grapple(hero, foe, Some(5), 2).unwrap();
+    encounter.new_round();
+    encounter.grapple_action(hero, GrappleAction::Throw).unwrap();
+    assert!(encounter.grapple_of(foe).is_none());
+    let foe_char = encounter.char_by_id(foe).unwrap();
+    assert_eq!(foe_char.conditions.iter().map(|x| x.name.as_str()).collect::<Vec<_>>(), ["Prone"]);
+
+    //Only the grappler can let go, the victim has to win a contested roll
+    encounter.new_round();
+    encounter.set_stat(hero, Stat::Initiative, 10).unwrap();
+    encounter.grapple(hero, foe, Some(5), 2).unwrap();
+    assert_eq!(encounter.release(foe), Err(CombatError::NotGrappling("Foe".into())));
+    assert_eq!(encounter.escape(hero, true), Err(CombatError::NotHeld("Hero".into())));
+    encounter.escape(foe, false).unwrap();
+    assert!(encounter.char_by_id(foe).unwrap().done);
+    assert!(encounter.grapple_of(foe).is_some());
+    encounter.new_round();
+    encounter.escape(foe, true).unwrap();
+    assert!(encounter.grapples().is_empty());
+    assert_eq!(encounter.log_iter().last().unwrap().to_string(), "Hero no longer holds Foe.");
+
+    encounter.new_round();
+    encounter.set_stat(hero, Stat::Initiative, 10).unwrap();
+    encounter.grapple(hero, foe, Some(5), 2).unwrap();
+    encounter.release(hero).unwrap();
+    assert!(encounter.grapples().is_empty());
+}
+
+#[test]
+fn grapple_conditions_follow_the_grapple() {
+    let mut encounter = skirmish();
+    let hero = id_of(&encounter, "Hero");
+    let foe = id_of(&encounter, "Foe");
+    //A Grappled condition put on by hand outlives the grapple
+    encounter.add_condition(foe, Condition::grappled()).unwrap();
+    encounter.grapple(hero, foe, Some(2), 2).unwrap();
+    assert_eq!(encounter.char_by_id(foe).unwrap().conditions.len(), 2);
+    encounter.release(hero).unwrap();
+    let conditions = &encounter.char_by_id(foe).unwrap().conditions;
+    assert_eq!(conditions.len(), 1);
+    assert_eq!(conditions[0].source, ConditionSource::Other);
+
+    //Taking off the grapple's condition ends the grapple
+    encounter.remove_condition(foe, "Grappled").unwrap();
+    encounter.new_round();
+    encounter.set_stat(hero, Stat::Initiative, 10).unwrap();
+    encounter.grapple(hero, foe, Some(2), 2).unwrap();
+    encounter.remove_condition(foe, "Grappled").unwrap();
+    assert!(encounter.grapples().is_empty());
+    assert!(encounter.char_by_id(foe).unwrap().conditions.is_empty());
+}
+
+#[test]
+fn gambit_rules() {
+    assert_eq!(Gambit::Disarm.difficulty(), 3);
+    assert_eq!(rules::gambit_cost(Gambit::Unhorse.difficulty()), 5);
+    assert!(rules::gambit_succeeds(2, Gambit::Knockdown.difficulty()));
+    assert!(!rules::gambit_succeeds(2, Gambit::Distract.difficulty()));
+    assert_eq!(Gambit::Knockdown.condition().unwrap().name, "Prone");
+    assert_eq!(Gambit::Knockdown.to_string(), "Knockdown (2)");
+}
+
+#[test]
+fn gambits_in_encounter() {
+    let mut encounter = skirmish();
+    let hero = id_of(&encounter, "Hero");
+    let foe = id_of(&encounter, "Foe");
+
+    //A successful gambit pays its cost, then resets the attacker like a decisive hit
+    encounter.gambit(hero, foe, Gambit::Disarm, Some(4)).unwrap();
+    assert_eq!(encounter.char_by_id(hero).unwrap().initiative, 3);
+    let paid = String::from("Hero pays 4 initiative for the gambit.");
+    assert!(encounter.log_iter().any(|x| x.to_string() == paid));
+    assert!(encounter.char_by_id(hero).unwrap().done);
+    let char = encounter.char_by_id(foe).unwrap();
+    assert_eq!(char.conditions[0].name, "Disarmed");
+    assert_eq!(char.pool_penalty(), -2);
+    assert_eq!(encounter.log_iter().last().unwrap().to_string(), "Hero disarms Foe!");
+
+    //Too few successes or a miss is a failed decisive attack
+    encounter.new_round();
+    assert!(!encounter.gambit(hero, foe, Gambit::Knockdown, Some(1)).unwrap());
+    assert_eq!(encounter.char_by_id(hero).unwrap().initiative, 1);
+    let last = encounter.log_iter().last().unwrap().to_string();
+    assert_eq!(last, "Hero fails to knock down Foe.");
+    //Gambits the attacker can not pay for are refused
+    assert_eq!(
+        encounter.gambit(hero, foe, Gambit::Knockdown, Some(3)),
+        Err(CombatError::NoInitiative("Hero".into()))
+    );
+    encounter.set_stat(hero, Stat::Initiative, -1).unwrap();
+    assert_eq!(
+        encounter.gambit(hero, foe, Gambit::Knockdown, Some(3)),
+        Err(CombatError::Crashed("Hero".into()))
+    );
+
+    encounter.set_stat(hero, Stat::Initiative, 12).unwrap();
+    //With this seed the rolled knockdown succeeds
+    assert!(encounter.rolled_gambit(hero, 0, foe, Gambit::Knockdown).unwrap());
+    assert!(encounter.char_by_id(foe).unwrap().conditions.iter().any(|x| x.name == "Prone"));
+    assert_eq!(encounter.char_by_id(hero).unwrap().initiative, 3);
+    encounter.undo();
+    assert_eq!(encounter.char_by_id(hero).unwrap().initiative, 12);
+}
+
+#[test]
+fn range_bands() {
+    assert_eq!(Range::default(), Range::Close);
+    assert_eq!(Range::Close.closer(), Range::Close);
+    assert_eq!(Range::Short.farther(), Range::Medium);
+    assert_eq!(Range::Extreme.farther(), Range::Extreme);
+    assert!(Range::Medium < Range::Long);
+    assert_eq!(Range::Extreme.abbrev(), 'X');
+
+    let band = RangeBand::new(3, 1, Range::Short);
+    assert!(band.between(1, 3) && band.between(3, 1));
+    assert!(band.involves(3) && !band.involves(2));
+
+    let text = r#"{"name": "Bow", "dice": 8, "damage": "10L", "range": "Long"}"#;
+    let bow: Attack = serde_json::from_str(text).unwrap();
+    assert_eq!(bow.max_range(), Range::Long);
+    let text = r#"{"name": "Sword", "dice": 8, "damage": "12L"}"#;
+    assert_eq!(serde_json::from_str::<Attack>(text).unwrap().max_range(), Range::Close);
+}
+
+#[test]
+fn movement() {
+    let mut encounter = skirmish();
+    let hero = id_of(&encounter, "Hero");
+    let foe = id_of(&encounter, "Foe");
+    let other = id_of(&encounter, "Other");
+    assert_eq!(encounter.range(hero, foe), Range::Close);
+
+    //Leaving close range takes a disengage
+    assert_eq!(
+        encounter.move_char(hero, foe, Movement::Farther),
+        Err(CombatError::MustDisengage("Hero".into()))
+    );
+    assert_eq!(encounter.move_char(hero, foe, Movement::Disengage(false)), Ok(Range::Close));
+    encounter.new_round();
+    assert_eq!(encounter.move_char(hero, foe, Movement::Disengage(true)), Ok(Range::Short));
+    assert!(encounter.char_by_id(hero).unwrap().done);
+    assert_eq!(encounter.move_char(hero, foe, Movement::Farther), Ok(Range::Medium));
+    assert_eq!(encounter.range(foe, hero), Range::Medium);
+    assert_eq!(encounter.range(other, hero), Range::Close);
+    let last = encounter.log_iter().last().unwrap().to_string();
+    assert_eq!(last, "Hero is now at medium range of Foe.");
+
+    //Melee attacks need close range, the bow reaches long range
+    assert_eq!(
+        encounter.rolled_withering_attack(hero, 0, foe),
+        Err(CombatError::OutOfRange("Foe".into(), Range::Medium))
+    );
+    assert_eq!(
+        encounter.grapple(hero, foe, Some(2), 1),
+        Err(CombatError::OutOfRange("Foe".into(), Range::Medium))
+    );
+    encounter.move_char(hero, foe, Movement::Set(Range::Extreme)).unwrap();
+    assert!(encounter.rolled_withering_attack(hero, 1, foe).is_err());
+    //Attacks typed in by hand reach as far as the attacker's longest attack
+    assert_eq!(
+        encounter.withering_attack(hero, foe, Some(2)),
+        Err(CombatError::OutOfRange("Foe".into(), Range::Extreme))
+    );
+    assert_eq!(
+        encounter.gambit(hero, foe, Gambit::Distract, Some(3)),
+        Err(CombatError::OutOfRange("Foe".into(), Range::Extreme))
+    );
+    encounter.undo();
+    encounter.set_stat(hero, Stat::Initiative, 5).unwrap();
+    assert_eq!(
+        encounter.decisive_attack(foe, hero, Some(2), DamageType::Lethal),
+        Err(CombatError::OutOfRange("Hero".into(), Range::Medium))
+    );
+    assert!(encounter.rolled_withering_attack(hero, 1, foe).is_ok());
+
+    encounter.new_round();
+    assert_eq!(encounter.move_char(foe, hero, Movement::Rush(false)), Ok(Range::Short));
+    encounter.new_round();
+    assert_eq!(encounter.move_char(foe, hero, Movement::Rush(true)), Ok(Range::Close));
+    let last = encounter.log_iter().last().unwrap().to_string();
+    assert_eq!(last, "Foe rushes Hero, now at close range.");
+
+    //Only opponents have to be disengaged from
+    encounter.set_side(other, Side::Players).unwrap();
+    assert_eq!(encounter.move_char(hero, other, Movement::Farther), Ok(Range::Short));
+    encounter.move_char(hero, other, Movement::Set(Range::Long)).unwrap();
+    encounter.remove_char(other).unwrap();
+    let saved = serde_json::to_string(&encounter).unwrap();
+    let restored: Encounter = serde_json::from_str(&saved).unwrap();
+    assert_eq!(restored.range(hero, foe), Range::Close);
+    assert_eq!(restored.range(hero, other), Range::Close);
+}
+
+fn soldiers() -> Character {
+    let mut char = Character::new(String::from("Soldiers"), 3, 7);
+    char.soak = 2;
+    char.parry = 2;
+    char.evasion = 1;
+    char.onslaught = -3;
+    char.side = Side::Enemies;
+    char.battle_group = Some(BattleGroup::new(2, Drill::Average, 1));
+    char
+}
+
+#[test]
+fn magnitude_and_size() {
+    let mut group = BattleGroup::new(2, Drill::Poor, 0);
+    group.restore(7);
+    assert_eq!(group.magnitude, 9);
+    group.take(7, 4);
+    assert_eq!((group.size, group.magnitude), (2, 5));
+
+    //Running out of Magnitude drops a Size and the excess is lost
+    group.take(7, 20);
+    assert_eq!((group.size, group.magnitude), (1, 8));
+    group.heal(7, 5);
+    assert_eq!(group.magnitude, 8);
+    group.take(7, 8);
+    assert_eq!((group.size, group.magnitude), (0, 0));
+    assert!(group.incapacitated());
+    assert_eq!(group.to_string(), "M0 S0");
+}
+
+#[test]
+fn battle_group_stats() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut group = soldiers();
+    group.reset(&mut rng);
+    assert_eq!(group.battle_group.as_ref().unwrap().magnitude, 9);
+
+    //Drill adds to defenses and onslaught is ignored
+    assert_eq!(group.effective_parry(), 3);
+    assert_eq!(group.effective_soak(), 4);
+    assert_eq!(group.attack_bonus(), 3);
+    assert_eq!(group.wound_penalty(), 0);
+
+    //Withering attacks take Magnitude and never crash
+    group.initiative = 1;
+    assert!(!group.take_withering_hit(1, 5));
+    assert_eq!(group.initiative, 1);
+    assert_eq!(group.battle_group.as_ref().unwrap().magnitude, 4);
+    assert_eq!(group.take_decisive_hit(3, DamageType::Lethal), 3);
+    assert_eq!(group.health.damage(), 0);
+    assert_eq!(group.health_status(), "M1 S2");
+}
+
+#[test]
+fn rout_check() {
+    let mut elite = BattleGroup::new(3, Drill::Elite, 0);
+    elite.willpower = 0;
+    elite.rout_check(&mut StdRng::seed_from_u64(1));
+    assert!(elite.routed);
+    assert!(elite.incapacitated());
+    elite.restore(7);
+    assert!(!elite.routed);
+}
+
+#[test]
+fn battle_group_in_encounter() {
+    let skirmish = |willpower| {
+        let mut group = soldiers();
+        group.battle_group.as_mut().unwrap().willpower = willpower;
+        let mut hero = Character::new(String::from("Hero"), 5, 7);
+        hero.side = Side::Players;
+        Encounter::from_characters(vec![hero, group], 3)
+    };
 
-    defender.do_withering_hit(12, true);
-    attacker.take_withering_hit("Defender".into(), 12);
+    //Without Willpower dice the group routs as soon as it loses a Size
+    let mut encounter = skirmish(0);
+    let (hero, group) = (id_of(&encounter, "Hero"), id_of(&encounter, "Soldiers"));
+    encounter.withering_attack(hero, group, Some(12)).unwrap();
+    let events: Vec<String> = encounter.log_iter().map(|x| x.to_string()).collect();
+    assert!(events.contains(&String::from("Soldiers is reduced to size 1.")));
+    assert!(events.iter().any(|x| x.starts_with("Soldiers routs!")));
+    assert!(!encounter.log_iter().any(|x| matches!(x, CombatEvent::Crash { .. })));
+    assert!(encounter.char_by_id(group).unwrap().incapacitated());
+    assert_eq!(encounter.winner(), Some(Side::Players));
 
-    //Measure initiative shift somehow
+    //A group that holds together fights on until it is wiped out
+    let mut encounter = skirmish(20);
+    let (hero, group) = (id_of(&encounter, "Hero"), id_of(&encounter, "Soldiers"));
+    encounter.withering_attack(hero, group, Some(12)).unwrap();
+    let events: Vec<String> = encounter.log_iter().map(|x| x.to_string()).collect();
+    assert!(events.iter().any(|x| x.starts_with("Soldiers holds together.")));
+    assert!(!encounter.char_by_id(group).unwrap().incapacitated());
+    assert_eq!(encounter.winner(), None);
+    encounter.change_health(group, 8, DamageType::Lethal).unwrap();
+    assert!(encounter.log_iter().any(|x| x.to_string() == "Soldiers is wiped out!"));
+    assert!(encounter.char_by_id(group).unwrap().incapacitated());
+    assert_eq!(encounter.winner(), Some(Side::Players));
 }
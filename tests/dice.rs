@@ -0,0 +1,50 @@
+use exalted_combat::dice::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+#[test]
+fn default_pool_scoring() {
+    let roll = DicePool::new(5).score(vec![1, 6, 7, 9, 10], vec![]);
+    assert_eq!(roll.successes, 4);
+    assert!(!roll.botch);
+    assert_eq!(roll.net(), 4);
+}
+
+#[test]
+fn botch_needs_a_one() {
+    let roll = DicePool::new(3).score(vec![1, 1, 4], vec![]);
+    assert_eq!(roll.successes, 0);
+    assert!(roll.botch);
+    assert_eq!(roll.net(), -2);
+
+    let roll = DicePool::new(3).score(vec![2, 3, 4], vec![]);
+    assert!(!roll.botch);
+    assert_eq!(roll.net(), 0);
+
+    //Automatic successes prevent a botch
+    let roll = DicePool::new(3).automatic(1).score(vec![1, 1, 4], vec![]);
+    assert_eq!(roll.successes, 1);
+    assert!(!roll.botch);
+}
+
+#[test]
+fn target_and_double_thresholds() {
+    let faces = vec![6, 7, 8, 9, 10];
+    assert_eq!(DicePool::new(5).target(6).score(faces.clone(), vec![]).successes, 6);
+    assert_eq!(DicePool::new(5).double(9).score(faces.clone(), vec![]).successes, 6);
+    assert_eq!(DicePool::new(5).double(8).score(faces.clone(), vec![]).successes, 7);
+    assert_eq!(DicePool::damage(5).score(faces, vec![]).successes, 4);
+}
+
+#[test]
+fn rerolled_faces_never_remain() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let pool = DicePool::new(50).reroll(1).reroll(6);
+    for _ in 0..20 {
+        let roll = pool.roll(&mut rng);
+        assert_eq!(roll.faces.len(), 50);
+        assert!(!roll.faces.contains(&1));
+        assert!(!roll.faces.contains(&6));
+        assert!(roll.rerolled.iter().all(|x| *x == 1 || *x == 6));
+    }
+}